@@ -1,39 +1,145 @@
 #![no_std]
 
-use gmeta::Metadata;
-use gstd::prelude::*;
+use gmeta::{In, InOut, Metadata, Out};
+use gstd::{collections::BTreeMap, prelude::*, ActorId};
+use store_io::{AttributeId, Effect, StoreError, TransactionId};
+
+pub use store_io::Slot;
 
 #[derive(Default, Encode, Decode, TypeInfo)]
 #[codec(crate = gstd::codec)]
 #[scale_info(crate = gstd::scale_info)]
 pub struct Tamagotchi {
-    // TODO: 0️⃣ Copy fields from previous lesson and push changes to the master branch
-    // TODO: 2️⃣ Add new fields
+    pub name: String,
+    pub date_of_birth: u64,
+    pub owner: ActorId,
+    pub fed: u64,
+    pub fed_block: u64,
+    pub entertained: u64,
+    pub entertained_block: u64,
+    pub slept: u64,
+    pub slept_block: u64,
+    pub approved_account: Option<ActorId>,
+    pub ft_contract_id: ActorId,
+    pub transaction_id: TransactionId,
+    pub approve_transaction: Option<(TransactionId, ActorId, u128)>,
+    pub equipment: BTreeMap<Slot, Equipped>,
+    /// Battle power granted by the equipped attributes.
+    pub power: u64,
+}
+
+/// Attribute worn in an equipment slot.
+#[derive(Encode, Decode, Clone, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub struct Equipped {
+    pub store_id: ActorId,
+    pub attribute_id: AttributeId,
+    /// Effects of the attribute as of the latest refresh from the store.
+    pub effects: Vec<Effect>,
 }
 
 #[derive(Encode, Decode, TypeInfo)]
 #[codec(crate = gstd::codec)]
 #[scale_info(crate = gstd::scale_info)]
 pub enum TmgAction {
-    // TODO: 0️⃣ Copy actions from previous lesson and push changes to the master branch
-    // TODO: 3️⃣ Add new actions
+    Name,
+    Age,
+    Feed,
+    Entertain,
+    Sleep,
+    Transfer(ActorId),
+    Approve(ActorId),
+    RevokeApproval,
+    SetFTokenContract(ActorId),
+    ApproveTokens {
+        account: ActorId,
+        amount: u128,
+    },
+    BuyAttribute {
+        store_id: ActorId,
+        attribute_id: AttributeId,
+        max_price: Option<u128>,
+        coupon: Option<String>,
+    },
+    Equip {
+        store_id: ActorId,
+        attribute_id: AttributeId,
+        slot: Slot,
+    },
+    Unequip {
+        slot: Slot,
+    },
+    /// Uses up a consumable store item in `Feed`, `Entertain` or `Sleep`
+    /// depending on its category.
+    UseItem {
+        store_id: ActorId,
+        attribute_id: AttributeId,
+    },
 }
 
 #[derive(Encode, Decode, TypeInfo)]
 #[codec(crate = gstd::codec)]
 #[scale_info(crate = gstd::scale_info)]
 pub enum TmgEvent {
-    // TODO: 0️⃣ Copy events from previous lesson and push changes to the master branch
-    // TODO: 4️⃣ Add new events
+    Name(String),
+    Age(u64),
+    Fed,
+    Entertained,
+    Slept,
+    Transferred(ActorId),
+    Approved(ActorId),
+    ApprovalRevoked,
+    FTokenContractSet,
+    TokensApproved {
+        account: ActorId,
+        amount: u128,
+    },
+    ApprovalError,
+    AttributeBought(AttributeId),
+    CompletePrevPurchase(AttributeId),
+    ErrorDuringPurchase(TmgError),
+    Equipped {
+        slot: Slot,
+        attribute_id: AttributeId,
+    },
+    Unequipped {
+        slot: Slot,
+    },
+    /// `Equip` or `UseItem` failed.
+    ItemError(TmgError),
+}
+
+/// Why an action involving the store failed.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum TmgError {
+    /// The store rejected the action.
+    Store(StoreError),
+    /// The store failed to process the action or replied with an unexpected event.
+    StoreUnavailable,
+    /// The store couldn`t charge for the purchase.
+    PaymentFailed,
+    /// The store may have charged for the purchase but hasn`t granted the attribute yet.
+    /// Buying it again finishes the purchase without paying twice.
+    PurchasePending,
+    NotOwned(AttributeId),
+    /// Consumable attributes are used up by care actions instead of being equipped.
+    Consumable(AttributeId),
+    /// The attribute is worn in another slot or can`t be worn at all.
+    WrongSlot {
+        attribute_id: AttributeId,
+        slot: Slot,
+    },
 }
 
 pub struct ProgramMetadata;
 
-// TODO: 0️⃣ Copy `Metadata` from the first lesson and push changes to the master branch
 impl Metadata for ProgramMetadata {
-    type Init = ();
-    type Handle = ();
-    type State = ();
+    type Init = In<String>;
+    type Handle = InOut<TmgAction, TmgEvent>;
+    type State = Out<Tamagotchi>;
     type Reply = ();
     type Others = ();
     type Signal = ();
@@ -1,22 +1,393 @@
 #![no_std]
 
-#[allow(unused_imports)]
-use gstd::prelude::*;
+use gstd::{exec, msg, prelude::*, ActorId};
+use sharded_fungible_token_io::{FTokenAction, FTokenEvent, LogicAction};
+use store_io::{AttributeId, Category, Effect, StoreAction, StoreError, StoreEvent, TxState};
+use tamagotchi_shop_io::{Equipped, Slot, Tamagotchi, TmgAction, TmgError, TmgEvent};
 
-// TODO: 5️⃣ Add the `approve_tokens` function
+const HUNGER_PER_BLOCK: u64 = 1;
+const BOREDOM_PER_BLOCK: u64 = 2;
+const ENERGY_PER_BLOCK: u64 = 2;
+const FILL_PER_FEED: u64 = 1000;
+const FILL_PER_ENTERTAINMENT: u64 = 1000;
+const FILL_PER_SLEEP: u64 = 1000;
+/// Energy a tamagotchi can store without `EnergyCap` effects.
+const ENERGY_CAP: u64 = 10_000;
+
+static mut TAMAGOTCHI: Option<Tamagotchi> = None;
 
 #[no_mangle]
 extern fn init() {
-    // TODO: 0️⃣ Copy the `init` function from the previous lesson and push changes to the master branch
+    let name: String = msg::load().expect("Can't decode the init message");
+    let block = exec::block_height() as u64;
+
+    let tamagotchi = Tamagotchi {
+        name,
+        date_of_birth: exec::block_timestamp(),
+        owner: msg::source(),
+        fed: 1,
+        fed_block: block,
+        entertained: 1,
+        entertained_block: block,
+        slept: 1,
+        slept_block: block,
+        ..Default::default()
+    };
+
+    unsafe { TAMAGOTCHI = Some(tamagotchi) }
 }
 
-#[no_mangle]
-extern fn handle() {
-    // TODO: 0️⃣ Copy the `handle` function from the previous lesson and push changes to the master branch
-    // TODO; 6️⃣ Add handling new actions
+#[gstd::async_main]
+async fn main() {
+    let action: TmgAction = msg::load().expect("Error in loading Tmg Input Message");
+    let tmg = unsafe {
+        TAMAGOTCHI
+            .as_mut()
+            .expect("The contract is not initialized")
+    };
+    match action {
+        TmgAction::Name => {
+            msg::reply(TmgEvent::Name(tmg.name.clone()), 0).expect("Name not loaded correctly");
+        }
+        TmgAction::Age => {
+            let age = exec::block_timestamp() - tmg.date_of_birth;
+            msg::reply(TmgEvent::Age(age), 0).expect("Age not loaded correctly");
+        }
+        TmgAction::Feed => {
+            refresh_equipment(tmg).await;
+            feed(tmg, FILL_PER_FEED);
+        }
+        TmgAction::Entertain => entertain(tmg, FILL_PER_ENTERTAINMENT),
+        TmgAction::Sleep => {
+            refresh_equipment(tmg).await;
+            sleep(tmg, FILL_PER_SLEEP);
+        }
+        TmgAction::Transfer(new_owner) => {
+            assert_owner(tmg);
+            tmg.owner = new_owner;
+            msg::reply(TmgEvent::Transferred(tmg.owner), 0)
+                .expect("Transference not executed correctly");
+        }
+        TmgAction::Approve(approved_account) => {
+            assert_owner(tmg);
+            tmg.approved_account = Some(approved_account);
+            msg::reply(TmgEvent::Approved(approved_account), 0).expect("Account approval failed");
+        }
+        TmgAction::RevokeApproval => {
+            assert_owner(tmg);
+            tmg.approved_account = None;
+            msg::reply(TmgEvent::ApprovalRevoked, 0).expect("Approval Revoke failed");
+        }
+        TmgAction::SetFTokenContract(ft_contract_id) => {
+            assert_owner(tmg);
+            tmg.ft_contract_id = ft_contract_id;
+            msg::reply(TmgEvent::FTokenContractSet, 0)
+                .expect("Error in sending a reply `TmgEvent::FTokenContractSet`");
+        }
+        TmgAction::ApproveTokens { account, amount } => {
+            assert_owner(tmg);
+            approve_tokens(tmg, account, amount).await;
+        }
+        TmgAction::BuyAttribute {
+            store_id,
+            attribute_id,
+            max_price,
+            coupon,
+        } => {
+            assert_owner(tmg);
+            buy_attribute(store_id, attribute_id, max_price, coupon).await;
+        }
+        TmgAction::Equip {
+            store_id,
+            attribute_id,
+            slot,
+        } => {
+            assert_owner(tmg);
+            let event = equip(tmg, store_id, attribute_id, slot)
+                .await
+                .unwrap_or_else(TmgEvent::ItemError);
+            msg::reply(event, 0).expect("Error in sending a reply `TmgEvent::Equipped`");
+        }
+        TmgAction::UseItem {
+            store_id,
+            attribute_id,
+        } => {
+            assert_owner(tmg);
+            use_item(tmg, store_id, attribute_id).await;
+        }
+        TmgAction::Unequip { slot } => {
+            assert_owner(tmg);
+            tmg.equipment.remove(&slot);
+            update_power(tmg);
+            msg::reply(TmgEvent::Unequipped { slot }, 0)
+                .expect("Error in sending a reply `TmgEvent::Unequipped`");
+        }
+    }
+}
+
+fn feed(tmg: &mut Tamagotchi, fill: u64) {
+    let slowdown = bonus(tmg, |effect| match effect {
+        Effect::SlowerHunger(percent) => percent.into(),
+        _ => 0,
+    })
+    .min(100);
+    let hunger = decay(tmg.fed_block, HUNGER_PER_BLOCK).saturating_mul(100 - slowdown) / 100;
+    tmg.fed = refill(tmg.fed, hunger, fill);
+    tmg.fed_block = exec::block_height() as u64;
+    msg::reply(TmgEvent::Fed, 0).expect("Not fed correctly");
+}
+
+fn entertain(tmg: &mut Tamagotchi, fill: u64) {
+    let boredom = decay(tmg.entertained_block, BOREDOM_PER_BLOCK);
+    tmg.entertained = refill(tmg.entertained, boredom, fill);
+    tmg.entertained_block = exec::block_height() as u64;
+    msg::reply(TmgEvent::Entertained, 0).expect("Not entertained correctly");
+}
+
+fn sleep(tmg: &mut Tamagotchi, fill: u64) {
+    let energy_cap = ENERGY_CAP.saturating_add(bonus(tmg, |effect| match effect {
+        Effect::EnergyCap(cap) => cap,
+        _ => 0,
+    }));
+    let tiredness = decay(tmg.slept_block, ENERGY_PER_BLOCK);
+    tmg.slept = refill(tmg.slept, tiredness, fill).min(energy_cap);
+    tmg.slept_block = exec::block_height() as u64;
+    msg::reply(TmgEvent::Slept, 0).expect("Not slept correctly");
+}
+
+/// Returns the decay accumulated since `last_block`.
+fn decay(last_block: u64, decay_per_block: u64) -> u64 {
+    let blocks = (exec::block_height() as u64).saturating_sub(last_block);
+    blocks.saturating_mul(decay_per_block)
+}
+
+fn refill(value: u64, decay: u64, fill: u64) -> u64 {
+    value.saturating_sub(decay).saturating_add(fill)
+}
+
+/// Sums up the bonus of the equipped attributes' effects.
+fn bonus(tmg: &Tamagotchi, effect_bonus: impl Fn(Effect) -> u64) -> u64 {
+    tmg.equipment
+        .values()
+        .flat_map(|equipped| &equipped.effects)
+        .map(|effect| effect_bonus(*effect))
+        .fold(0, u64::saturating_add)
+}
+
+fn update_power(tmg: &mut Tamagotchi) {
+    tmg.power = bonus(tmg, |effect| match effect {
+        Effect::Power(power) => power,
+        _ => 0,
+    });
+}
+
+fn assert_owner(tmg: &Tamagotchi) {
+    assert_eq!(
+        msg::source(),
+        tmg.owner,
+        "Only the owner of the Tamagotchi can do this"
+    );
+}
+
+async fn approve_tokens(tmg: &mut Tamagotchi, account: ActorId, amount: u128) {
+    let transaction_id = match tmg.approve_transaction {
+        // the previous approval didn`t complete, so it is retried with the same ID
+        Some((transaction_id, prev_account, prev_amount))
+            if prev_account == account && prev_amount == amount =>
+        {
+            transaction_id
+        }
+        Some(_) => {
+            msg::reply(TmgEvent::ApprovalError, 0)
+                .expect("Error in sending a reply `TmgEvent::ApprovalError`");
+            return;
+        }
+        None => {
+            let transaction_id = tmg.transaction_id;
+            tmg.transaction_id = tmg.transaction_id.wrapping_add(1);
+            tmg.approve_transaction = Some((transaction_id, account, amount));
+            transaction_id
+        }
+    };
+
+    let reply = msg::send_for_reply_as::<_, FTokenEvent>(
+        tmg.ft_contract_id,
+        FTokenAction::Message {
+            transaction_id,
+            payload: LogicAction::Approve {
+                approved_account: account,
+                amount,
+            },
+        },
+        0,
+        0,
+    )
+    .expect("Error in sending a message `FTokenAction::Message`")
+    .await;
+    tmg.approve_transaction = None;
+
+    let event = match reply {
+        Ok(FTokenEvent::Ok) => TmgEvent::TokensApproved { account, amount },
+        _ => TmgEvent::ApprovalError,
+    };
+    msg::reply(event, 0).expect("Error in sending a reply `TmgEvent::TokensApproved`");
+}
+
+/// Buys an attribute with the attached value, if any, and returns the change to the owner.
+async fn buy_attribute(
+    store_id: ActorId,
+    attribute_id: AttributeId,
+    max_price: Option<u128>,
+    coupon: Option<String>,
+) {
+    let reply = request_store(
+        store_id,
+        StoreAction::BuyAttribute {
+            attribute_id,
+            max_price,
+            coupon,
+        },
+        msg::value(),
+    )
+    .await;
+
+    // the store returns the whole value unless it sells the attribute for it
+    let (event, change) = match reply {
+        Ok(StoreEvent::AttributeSold { state, change }) => {
+            let event = match state {
+                TxState::Completed => TmgEvent::AttributeBought(attribute_id),
+                TxState::Failed => TmgEvent::ErrorDuringPurchase(TmgError::PaymentFailed),
+                TxState::Pending | TxState::Paid => {
+                    TmgEvent::ErrorDuringPurchase(TmgError::PurchasePending)
+                }
+            };
+            (event, change)
+        }
+        Err(TmgError::Store(StoreError::CompletePrevTx { attribute_id })) => {
+            (TmgEvent::CompletePrevPurchase(attribute_id), msg::value())
+        }
+        Ok(_) => (
+            TmgEvent::ErrorDuringPurchase(TmgError::StoreUnavailable),
+            msg::value(),
+        ),
+        Err(error) => (TmgEvent::ErrorDuringPurchase(error), msg::value()),
+    };
+    msg::reply(event, change).expect("Error in sending a reply `TmgEvent::AttributeBought`");
+}
+
+async fn equip(
+    tmg: &mut Tamagotchi,
+    store_id: ActorId,
+    attribute_id: AttributeId,
+    slot: Slot,
+) -> Result<TmgEvent, TmgError> {
+    let StoreEvent::Attributes { attributes } = request_store(
+        store_id,
+        StoreAction::GetAttributes {
+            tamagotchi_id: exec::program_id(),
+        },
+        0,
+    )
+    .await?
+    else {
+        return Err(TmgError::StoreUnavailable);
+    };
+    if !attributes.contains(&attribute_id) {
+        return Err(TmgError::NotOwned(attribute_id));
+    }
+
+    let StoreEvent::Attribute { attr_metadata, .. } =
+        request_store(store_id, StoreAction::GetAttribute { attribute_id }, 0).await?
+    else {
+        return Err(TmgError::StoreUnavailable);
+    };
+    // consumables are used up by care actions instead of being worn
+    if attr_metadata.category.is_consumable() {
+        return Err(TmgError::Consumable(attribute_id));
+    }
+    // each attribute has its own slot, so bonuses of several swords never stack
+    if attr_metadata.slot != Some(slot) {
+        return Err(TmgError::WrongSlot { attribute_id, slot });
+    }
+
+    // an attribute can be worn in a single slot only
+    tmg.equipment.retain(|_, equipped| {
+        equipped.store_id != store_id || equipped.attribute_id != attribute_id
+    });
+    tmg.equipment.insert(
+        slot,
+        Equipped {
+            store_id,
+            attribute_id,
+            effects: attr_metadata.effects,
+        },
+    );
+    update_power(tmg);
+
+    Ok(TmgEvent::Equipped { slot, attribute_id })
+}
+
+/// Re-reads the effects of the equipped attributes, so catalog updates reach the pets wearing them.
+///
+/// An attribute keeps its previous effects if the store can`t be reached.
+async fn refresh_equipment(tmg: &mut Tamagotchi) {
+    for equipped in tmg.equipment.values_mut() {
+        if let Ok(StoreEvent::Attribute { attr_metadata, .. }) = request_store(
+            equipped.store_id,
+            StoreAction::GetAttribute {
+                attribute_id: equipped.attribute_id,
+            },
+            0,
+        )
+        .await
+        {
+            equipped.effects = attr_metadata.effects;
+        }
+    }
+    update_power(tmg);
+}
+
+/// Consumes one unit of a store item and applies it in the matching care action.
+async fn use_item(tmg: &mut Tamagotchi, store_id: ActorId, attribute_id: AttributeId) {
+    let category =
+        match request_store(store_id, StoreAction::UseAttribute { attribute_id }, 0).await {
+            Ok(StoreEvent::AttributeUsed { category, .. }) => category,
+            reply => {
+                let error = reply.err().unwrap_or(TmgError::StoreUnavailable);
+                msg::reply(TmgEvent::ItemError(error), 0)
+                    .expect("Error in sending a reply `TmgEvent::ItemError`");
+                return;
+            }
+        };
+
+    refresh_equipment(tmg).await;
+    // an item is never worse than the basic care action
+    match category {
+        Category::Food(fill) => feed(tmg, fill.max(FILL_PER_FEED)),
+        Category::Toy(fill) => entertain(tmg, fill.max(FILL_PER_ENTERTAINMENT)),
+        Category::Pillow(fill) => sleep(tmg, fill.max(FILL_PER_SLEEP)),
+        Category::Cosmetic => unreachable!("The store only uses up consumable attributes"),
+    }
+}
+
+async fn request_store(
+    store_id: ActorId,
+    action: StoreAction,
+    value: u128,
+) -> Result<StoreEvent, TmgError> {
+    msg::send_for_reply_as::<_, Result<StoreEvent, StoreError>>(store_id, action, value, 0)
+        .expect("Error in sending a message `StoreAction`")
+        .await
+        .map_err(|_| TmgError::StoreUnavailable)?
+        .map_err(TmgError::Store)
 }
 
 #[no_mangle]
 extern fn state() {
-    // TODO: 0️⃣ Copy the `handle` function from the previous lesson and push changes to the master branch
+    let tmg = unsafe {
+        TAMAGOTCHI
+            .as_ref()
+            .expect("The contract is not initialized")
+    };
+    msg::reply(tmg, 0).expect("Failed to share state");
 }
@@ -1,26 +1,109 @@
 #![no_std]
 
-use gmeta::{In, InOut, Metadata as GMetadata, Out};
+use gmeta::{In, InOut, Metadata as GMetadata};
 use gstd::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     prelude::*,
-    ActorId,
+    ActorId, CodeId, MessageId,
 };
 
 pub type AttributeId = u32;
 pub type Price = u128;
+pub type Quantity = u32;
 pub type TamagotchiId = ActorId;
 pub type TransactionId = u64;
 
+pub const MAX_TITLE_LEN: usize = 64;
+pub const MAX_DESCRIPTION_LEN: usize = 512;
+pub const MAX_MEDIA_LEN: usize = 256;
+
 pub struct ProgramMetadata;
 
 impl GMetadata for ProgramMetadata {
-    type Init = In<ActorId>;
-    type Handle = InOut<StoreAction, StoreEvent>;
+    type Init = In<InitStore>;
+    type Handle = InOut<StoreAction, Result<StoreEvent, StoreError>>;
     type Reply = ();
     type Others = ();
-    type Signal = ();
-    type State = Out<AttributeStore>;
+    type Signal = StoreEvent;
+    type State = InOut<StateQuery, StateReply>;
+}
+
+/// Selects the part of the state to read, so readers don`t have to fetch the whole store.
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum StateQuery {
+    Full,
+    Catalog {
+        offset: u32,
+        limit: u32,
+    },
+    /// Storage holding the attributes of a tamagotchi.
+    ///
+    /// State can`t be read across programs, so the attributes are read in two steps: this query
+    /// finds the storage, and `StorageQuery::AttributesOf` then reads them from its state.
+    AttributesStorageOf {
+        tamagotchi_id: TamagotchiId,
+    },
+    PendingTx {
+        tamagotchi_id: TamagotchiId,
+    },
+    /// A page of the purchase log, oldest first.
+    Purchases {
+        offset: u32,
+        limit: u32,
+    },
+    Sales {
+        attribute_id: AttributeId,
+    },
+}
+
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum StateReply {
+    Full(AttributeStore),
+    /// Attributes for sale and retired ones, with their current price and currency.
+    Catalog {
+        attributes: Vec<CatalogEntry>,
+    },
+    /// `None` if no attribute was ever granted to the shard of the tamagotchi.
+    AttributesStorageOf {
+        storage: Option<ActorId>,
+    },
+    PendingTx {
+        transaction: Option<Box<Transaction>>,
+    },
+    Purchases {
+        purchases: Vec<Purchase>,
+    },
+    /// Zero counters if the attribute was never sold.
+    Sales {
+        sales: Sales,
+    },
+}
+
+/// Attribute as listed in the catalog.
+#[derive(Encode, Decode, Clone, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub struct CatalogEntry {
+    pub attribute_id: AttributeId,
+    pub attr_metadata: AttrMetadata,
+    /// Current price, including the bonding curve premium.
+    pub price: Price,
+    pub currency: Currency,
+    /// Retired attributes are listed so their owners can resolve the metadata, but aren`t for sale.
+    pub retired: bool,
+}
+
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub struct InitStore {
+    pub ft_contract_id: ActorId,
+    /// Code of the storage programs the store creates to hold ownership shards.
+    pub storage_code_id: CodeId,
 }
 
 #[derive(Default, Encode, Decode, TypeInfo)]
@@ -28,11 +111,140 @@ impl GMetadata for ProgramMetadata {
 #[scale_info(crate = gstd::scale_info)]
 pub struct AttributeStore {
     pub admin: ActorId,
+    /// Account the admin rights are offered to until it accepts them.
+    pub pending_admin: Option<ActorId>,
+    pub roles: BTreeMap<ActorId, BTreeSet<Role>>,
     pub ft_contract_id: ActorId,
     pub attributes: BTreeMap<AttributeId, (AttrMetadata, Price)>,
-    pub owners: BTreeMap<TamagotchiId, BTreeSet<AttributeId>>,
+    pub retired: BTreeSet<AttributeId>,
+    /// Attributes missing from this map are priced in the fungible token.
+    pub currencies: BTreeMap<AttributeId, Currency>,
+    pub storage_code_id: CodeId,
+    /// Storage programs holding the attributes owned by tamagotchis, by shard.
+    pub storages: BTreeMap<u8, ActorId>,
     pub transaction_id: TransactionId,
-    pub transactions: BTreeMap<TamagotchiId, (TransactionId, AttributeId)>,
+    pub transactions: BTreeMap<TamagotchiId, Transaction>,
+    /// Discount in percent applied to the total price of `BuyAttributes`.
+    pub bundle_discount: u8,
+    /// Total amount received for sold attributes in each currency.
+    pub revenue: BTreeMap<Currency, Price>,
+    /// While paused, attributes can be neither created nor bought,
+    /// but purchases that were already started can be retried.
+    pub paused: bool,
+    /// The latest purchases, oldest first.
+    pub purchases: VecDeque<Purchase>,
+    pub sales: BTreeMap<AttributeId, Sales>,
+    /// Program (e.g. a tamagotchi factory) that registers genuine tamagotchis.
+    /// If set, attributes can only be granted to registered tamagotchis.
+    pub registrar: Option<ActorId>,
+    pub registered: BTreeSet<TamagotchiId>,
+    pub curves: BTreeMap<AttributeId, PriceCurve>,
+    /// Amount added by the curve to the attribute price and the block it was last raised at.
+    pub premiums: BTreeMap<AttributeId, (Price, u32)>,
+    pub coupons: BTreeMap<String, Coupon>,
+    /// Program notified of catalog additions, sales, refunds and removed transactions.
+    pub listener: Option<ActorId>,
+    /// Permanent attributes being bought for each tamagotchi by unfinished purchases.
+    pub reserved: BTreeMap<TamagotchiId, BTreeSet<AttributeId>>,
+    /// Buyers whose transactions await a reply to the token transfer or the grant with this ID.
+    pub pending_replies: BTreeMap<MessageId, TamagotchiId>,
+}
+
+#[derive(Encode, Decode, Clone, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub struct Purchase {
+    pub buyer: ActorId,
+    pub recipient: TamagotchiId,
+    pub attribute_id: AttributeId,
+    pub price: Price,
+    pub currency: Currency,
+    pub transaction_id: TransactionId,
+    pub block: u32,
+}
+
+/// Sales counters of a single attribute.
+#[derive(Default, Encode, Decode, Clone, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub struct Sales {
+    pub units_sold: u64,
+    /// Revenue in the currency the attribute was priced in at the moment of each sale.
+    pub revenue: Price,
+}
+
+#[derive(Encode, Decode, Clone, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub struct Transaction {
+    pub id: TransactionId,
+    pub attribute_ids: Vec<AttributeId>,
+    pub recipient: TamagotchiId,
+    /// Prices of `attribute_ids` quoted when the transaction was started, so a retry pays the same.
+    pub prices: Vec<Price>,
+    /// Coupon redeemed for the quoted prices.
+    pub coupon: Option<String>,
+    /// Currency of the prices, kept even if the attributes are repriced in another one.
+    pub currency: Currency,
+    /// Block height at which the transaction was started.
+    pub created_at: u32,
+    pub state: TxState,
+    /// The latest purchase message working on the transaction.
+    pub message_id: MessageId,
+    /// Token transfer awaiting a reply.
+    pub transfer_id: Option<MessageId>,
+    /// Grant sent to the storage of the recipient, awaiting a reply.
+    pub grant_id: Option<MessageId>,
+}
+
+/// Progress of a purchase.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum TxState {
+    /// The tokens are being transferred. A retry sends the transfer again with the same ID.
+    Pending,
+    /// The tokens or the native value were received and the attributes are being granted.
+    /// A retry or `DeliverTx` grants them again if the previous grant failed.
+    Paid,
+    /// The storage confirmed the grant. Kept only if the purchase couldn`t reply or the
+    /// attributes were granted by `DeliverTx`, so that a retry gets the outcome without paying again.
+    Completed,
+    /// The tokens weren`t transferred.
+    Failed,
+}
+
+/// Promo code discount on a single attribute purchase.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub struct Coupon {
+    pub discount: Discount,
+    /// Number of purchases the coupon can still be redeemed for.
+    pub uses_left: u32,
+    /// Block height from which the coupon can no longer be redeemed.
+    pub expires_at: u32,
+    /// If set, the coupon only applies to this attribute.
+    pub attribute_id: Option<AttributeId>,
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum Discount {
+    Percent(u8),
+    Fixed(Price),
+}
+
+/// Bonding curve that raises the price of an attribute with each sale.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub struct PriceCurve {
+    /// Added to the price on each sold unit.
+    pub step: Price,
+    /// Taken off the added amount each block until the base price is reached again.
+    pub decay_per_block: Price,
 }
 
 #[derive(Encode, Decode, Clone, TypeInfo, Debug)]
@@ -41,7 +253,162 @@ pub struct AttributeStore {
 pub struct AttrMetadata {
     pub title: String,
     pub description: String,
-    pub media: String,
+    pub media: Media,
+    pub effects: Vec<Effect>,
+    pub category: Category,
+    /// Equipment slot a tamagotchi wears the attribute in, `None` if it can`t be worn.
+    pub slot: Option<Slot>,
+}
+
+impl AttrMetadata {
+    pub fn validate(&self) -> Result<(), MetadataError> {
+        if self.title.trim().is_empty() {
+            return Err(MetadataError::EmptyTitle);
+        }
+        if self.title.len() > MAX_TITLE_LEN {
+            return Err(MetadataError::TitleTooLong);
+        }
+        if self.description.len() > MAX_DESCRIPTION_LEN {
+            return Err(MetadataError::DescriptionTooLong);
+        }
+        if !self.media.is_valid() {
+            return Err(MetadataError::InvalidMedia);
+        }
+        if self.category.is_consumable() && self.slot.is_some() {
+            return Err(MetadataError::WearableConsumable);
+        }
+        Ok(())
+    }
+}
+
+/// Where the frontend takes the attribute image from.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum Media {
+    /// IPFS content identifier, without the `ipfs://` prefix.
+    Ipfs(String),
+    /// Full `https://` URL.
+    Https(String),
+    /// One of the images bundled with the frontend.
+    Sprite(Sprite),
+}
+
+impl Media {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Ipfs(cid) => {
+                !cid.is_empty()
+                    && cid.len() <= MAX_MEDIA_LEN
+                    && cid.chars().all(|c| c.is_ascii_alphanumeric())
+            }
+            Self::Https(url) => {
+                url.len() > "https://".len()
+                    && url.len() <= MAX_MEDIA_LEN
+                    && url.starts_with("https://")
+                    && url.chars().all(|c| c.is_ascii_graphic())
+            }
+            Self::Sprite(_) => true,
+        }
+    }
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum Sprite {
+    Sword,
+    Hat,
+    Bag,
+    Glasses,
+}
+
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum MetadataError {
+    EmptyTitle,
+    TitleTooLong,
+    DescriptionTooLong,
+    InvalidMedia,
+    /// Consumables are used up by care actions, so they can`t have an equipment slot.
+    WearableConsumable,
+}
+
+#[derive(Default, Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum Category {
+    /// Permanent item that can be owned only once.
+    #[default]
+    Cosmetic,
+    /// Used up by feeding, filling the given amount.
+    Food(u64),
+    /// Used up by entertaining, filling the given amount.
+    Toy(u64),
+    /// Used up by sleeping, filling the given amount.
+    Pillow(u64),
+}
+
+impl Category {
+    pub fn is_consumable(&self) -> bool {
+        *self != Self::Cosmetic
+    }
+}
+
+/// Equipment slots matching the store items shown by the frontend.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum Slot {
+    /// Sword.
+    Weapon,
+    /// Hat.
+    Head,
+    /// Bag.
+    Bag,
+    /// Glasses.
+    Eyes,
+}
+
+/// Bonuses the tamagotchi gets while the attribute is equipped.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum Effect {
+    /// Adds to the power used in battles.
+    Power(u64),
+    /// Raises the energy the tamagotchi can store above the base cap.
+    /// The bonuses of several equipped attributes add up.
+    EnergyCap(u64),
+    /// Makes the tamagotchi get hungry slower, in percent.
+    SlowerHunger(u8),
+}
+
+#[derive(Default, Encode, Decode, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum Currency {
+    /// Paid with the fungible token at `ft_contract_id`.
+    #[default]
+    FungibleToken,
+    /// Paid with the fungible token at the given address, e.g. a seasonal event token.
+    Token(ActorId),
+    /// Paid with the value attached to the `BuyAttribute` message.
+    Native,
+}
+
+/// Permissions that the admin can delegate without handing over full control.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, TypeInfo, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum Role {
+    /// Creates, updates, reprices and retires attributes.
+    CatalogManager,
+    /// Sets the fungible token contract and withdraws revenue.
+    Treasurer,
+    /// Removes stuck transactions.
+    TxOperator,
 }
 
 #[derive(Encode, Decode, TypeInfo, Debug)]
@@ -53,8 +420,62 @@ pub enum StoreAction {
         attr_metadata: AttrMetadata,
         price: Price,
     },
+    /// Creates all the attributes or none of them.
+    CreateAttributes {
+        attributes: Vec<(AttributeId, AttrMetadata, Price)>,
+    },
+    UpdateAttribute {
+        attribute_id: AttributeId,
+        attr_metadata: AttrMetadata,
+    },
+    SetPrice {
+        attribute_id: AttributeId,
+        price: Price,
+    },
+    RetireAttribute {
+        attribute_id: AttributeId,
+    },
+    /// Switches the attribute to the bonding curve pricing, or back to the fixed price with `None`.
+    SetPriceCurve {
+        attribute_id: AttributeId,
+        curve: Option<PriceCurve>,
+    },
+    GetPrice {
+        attribute_id: AttributeId,
+    },
+    SetCurrency {
+        attribute_id: AttributeId,
+        currency: Currency,
+    },
+    /// The purchase is rejected if the current price exceeds `max_price`.
     BuyAttribute {
         attribute_id: AttributeId,
+        max_price: Option<Price>,
+        coupon: Option<String>,
+    },
+    BuyAttributes {
+        attribute_ids: Vec<AttributeId>,
+    },
+    /// Pays for an attribute that is granted to `recipient`.
+    BuyAttributeFor {
+        attribute_id: AttributeId,
+        recipient: TamagotchiId,
+    },
+    /// Uses up one unit of a consumable attribute owned by the caller.
+    UseAttribute {
+        attribute_id: AttributeId,
+    },
+    GetAttribute {
+        attribute_id: AttributeId,
+    },
+    GetPurchases {
+        offset: u32,
+        limit: u32,
+    },
+    OwnersOf {
+        attribute_id: AttributeId,
+        offset: u32,
+        limit: u32,
     },
     GetAttributes {
         tamagotchi_id: TamagotchiId,
@@ -62,19 +483,272 @@ pub enum StoreAction {
     SetFtContractId {
         ft_contract_id: ActorId,
     },
+    SetBundleDiscount {
+        discount: u8,
+    },
+    CreateCoupon {
+        code: String,
+        coupon: Coupon,
+    },
+    RemoveCoupon {
+        code: String,
+    },
+    Withdraw {
+        currency: Currency,
+        to: ActorId,
+        amount: Price,
+    },
+    /// Removes a transaction the buyer surely wasn't charged for, or a finished one.
     RemoveTx {
         tamagotchi_id: TamagotchiId,
     },
+    /// Removes the caller's own pending transaction once it has expired.
+    ///
+    /// Only a transaction whose transfer was never sent can expire. One whose transfer was sent
+    /// can only be retried, and a paid one is finished with `DeliverTx`.
+    CancelTx,
+    /// Grants the attributes of the buyer's paid transaction.
+    ///
+    /// Anyone can send it, and the store sends it to itself if a purchase fails to grant
+    /// the attributes it was paid for.
+    DeliverTx {
+        buyer: ActorId,
+    },
+    /// Offers the admin rights to `new_admin`, who takes them over with `AcceptAdmin`.
+    TransferAdmin {
+        new_admin: ActorId,
+    },
+    AcceptAdmin,
+    GrantRole {
+        account: ActorId,
+        role: Role,
+    },
+    RevokeRole {
+        account: ActorId,
+        role: Role,
+    },
+    SetRegistrar {
+        registrar: Option<ActorId>,
+    },
+    SetListener {
+        listener: Option<ActorId>,
+    },
+    RegisterTamagotchi {
+        tamagotchi_id: TamagotchiId,
+    },
+    Pause,
+    Unpause,
 }
 
 #[derive(Encode, Decode, TypeInfo)]
 #[codec(crate = gstd::codec)]
 #[scale_info(crate = gstd::scale_info)]
 pub enum StoreEvent {
-    AttributeCreated { attribute_id: AttributeId },
-    AttributeSold { success: bool },
-    Attributes { attributes: BTreeSet<AttributeId> },
-    CompletePrevTx { attribute_id: AttributeId },
-    FtContractIdSet { ft_contract_id: ActorId },
-    TxRemoved { tamagotchi_id: ActorId },
+    AttributeCreated {
+        attribute_id: AttributeId,
+    },
+    AttributesCreated {
+        attribute_ids: Vec<AttributeId>,
+    },
+    AttributeUpdated {
+        attribute_id: AttributeId,
+    },
+    PriceSet {
+        attribute_id: AttributeId,
+        price: Price,
+    },
+    AttributeRetired {
+        attribute_id: AttributeId,
+    },
+    PriceCurveSet {
+        attribute_id: AttributeId,
+        curve: Option<PriceCurve>,
+    },
+    Price {
+        attribute_id: AttributeId,
+        price: Price,
+    },
+    CurrencySet {
+        attribute_id: AttributeId,
+        currency: Currency,
+    },
+    AttributeSold {
+        /// `Completed` if the attributes were granted, `Failed` if nothing was charged,
+        /// `Paid` or `Pending` if the payment may be taken and a retry must finish the purchase.
+        state: TxState,
+        /// Part of the attached value returned with the reply.
+        change: u128,
+    },
+    AttributesSold {
+        /// See `AttributeSold`.
+        state: TxState,
+        /// Part of the attached value returned with the reply.
+        change: u128,
+    },
+    AttributeSoldFor {
+        attribute_id: AttributeId,
+        payer: ActorId,
+        recipient: TamagotchiId,
+        /// See `AttributeSold`.
+        state: TxState,
+        /// Part of the attached value returned with the reply.
+        change: u128,
+    },
+    AttributeUsed {
+        attribute_id: AttributeId,
+        category: Category,
+        remaining: Quantity,
+    },
+    Attribute {
+        attr_metadata: AttrMetadata,
+        /// Current price, including the bonding curve premium.
+        price: Price,
+    },
+    Purchases {
+        purchases: Vec<Purchase>,
+    },
+    Owners {
+        attribute_id: AttributeId,
+        owners: Vec<TamagotchiId>,
+    },
+    Attributes {
+        attributes: BTreeSet<AttributeId>,
+    },
+    FtContractIdSet {
+        ft_contract_id: ActorId,
+    },
+    BundleDiscountSet {
+        discount: u8,
+    },
+    CouponCreated {
+        code: String,
+    },
+    CouponRemoved {
+        code: String,
+    },
+    Withdrawn {
+        currency: Currency,
+        to: ActorId,
+        amount: Price,
+        success: bool,
+    },
+    TxRemoved {
+        tamagotchi_id: ActorId,
+    },
+    /// Sent to the listener from `handle_signal` when a purchase message fails midway.
+    TxInterrupted {
+        buyer: ActorId,
+        transaction_id: TransactionId,
+        state: TxState,
+    },
+    TxDelivered {
+        buyer: ActorId,
+        transaction_id: TransactionId,
+        /// If `false`, the grant failed again and the transaction stays paid.
+        granted: bool,
+    },
+    AdminOffered {
+        new_admin: ActorId,
+    },
+    AdminTransferred {
+        new_admin: ActorId,
+    },
+    RoleGranted {
+        account: ActorId,
+        role: Role,
+    },
+    RoleRevoked {
+        account: ActorId,
+        role: Role,
+    },
+    RegistrarSet {
+        registrar: Option<ActorId>,
+    },
+    ListenerSet {
+        listener: Option<ActorId>,
+    },
+    /// Sent to the listener when value attached to a message is returned to its source.
+    Refunded {
+        to: ActorId,
+        amount: u128,
+    },
+    TamagotchiRegistered {
+        tamagotchi_id: TamagotchiId,
+    },
+    Paused,
+    Unpaused,
+}
+
+/// Reasons a store action is rejected with.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum StoreError {
+    /// The source has neither admin rights nor the role the action requires.
+    Unauthorized,
+    Paused,
+    AttributeAlreadyExists {
+        attribute_id: AttributeId,
+    },
+    AttributeNotFound {
+        attribute_id: AttributeId,
+    },
+    InvalidMetadata {
+        attribute_id: AttributeId,
+        error: MetadataError,
+    },
+    AttributeRetired {
+        attribute_id: AttributeId,
+    },
+    AlreadyOwned {
+        attribute_id: AttributeId,
+    },
+    /// Another purchase of the permanent attribute for the same tamagotchi isn`t finished yet.
+    PurchaseInProgress {
+        attribute_id: AttributeId,
+    },
+    NotOwned {
+        attribute_id: AttributeId,
+    },
+    NotConsumable {
+        attribute_id: AttributeId,
+    },
+    EmptyBundle,
+    DuplicateAttributes,
+    MixedCurrencies,
+    PriceExceedsMax {
+        price: Price,
+        max_price: Price,
+    },
+    InsufficientValue {
+        price: Price,
+        value: u128,
+    },
+    InvalidDiscount {
+        discount: u8,
+    },
+    CouponAlreadyExists,
+    CouponNotFound,
+    CouponExpired,
+    CouponExhausted,
+    /// The coupon is scoped to another attribute.
+    CouponNotApplicable,
+    /// The source has a pending transaction for another purchase that must be completed first.
+    CompletePrevTx {
+        attribute_id: AttributeId,
+    },
+    /// Same as `CompletePrevTx`, but for a pending bundle purchase.
+    CompletePrevBundleTx {
+        attribute_ids: Vec<AttributeId>,
+    },
+    NoPendingTx,
+    /// The buyer has no paid transaction awaiting its attributes.
+    NoPaidTx,
+    TxNotExpired,
+    /// The tokens of the transaction may already be transferred, so it can only be retried.
+    TxInProgress,
+    /// The recipient isn`t a registered tamagotchi.
+    UnknownTamagotchi {
+        tamagotchi_id: TamagotchiId,
+    },
 }
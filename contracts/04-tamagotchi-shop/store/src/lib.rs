@@ -1,26 +1,64 @@
 #![no_std]
 
 use gstd::{
-    collections::{BTreeMap, BTreeSet},
-    exec, msg,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    exec,
+    msg::{self, CodecMessageFuture},
     prelude::*,
-    ActorId,
+    prog::ProgramGenerator,
+    ActorId, CodeId, MessageId,
 };
 use sharded_fungible_token_io::{FTokenAction, FTokenEvent, LogicAction};
 use store_io::{
-    AttrMetadata, AttributeId, Price, StoreAction, StoreEvent, TamagotchiId, TransactionId,
+    AttrMetadata, AttributeId, CatalogEntry, Coupon, Currency, Discount, InitStore, Price,
+    PriceCurve, Purchase, Quantity, Role, Sales, StateQuery, StateReply, StoreAction, StoreError,
+    StoreEvent, TamagotchiId, Transaction, TransactionId, TxState,
 };
+use store_storage_io::{StorageAction, StorageEvent};
+
+/// Number of blocks after which a pending transaction is considered stuck.
+const TX_LIFETIME: u32 = 100;
+/// Number of the latest purchases kept in the purchase log.
+const MAX_PURCHASES: usize = 1000;
+/// Gas reserved for `handle_reply` to record the outcome of a token transfer, a grant
+/// or a storage creation.
+const REPLY_DEPOSIT: u64 = 1_000_000_000;
+/// Gas reserved for `handle_signal` to report a failed purchase.
+const SIGNAL_GAS: u64 = 1_000_000_000;
+/// Gas given to a storage program for its initialization.
+const STORAGE_GAS: u64 = 1_000_000_000;
+/// Number of blocks after which the store grants the attributes of a paid transaction itself
+/// if the purchase failed to grant them.
+const REDELIVERY_DELAY: u32 = 10;
 
 static mut STORE: Option<AttributeStore> = None;
 
 #[derive(Default)]
 struct AttributeStore {
     admin: ActorId,
+    pending_admin: Option<ActorId>,
+    roles: BTreeMap<ActorId, BTreeSet<Role>>,
     ft_contract_id: ActorId,
     attributes: BTreeMap<AttributeId, (AttrMetadata, Price)>,
-    owners: BTreeMap<TamagotchiId, BTreeSet<AttributeId>>,
+    retired: BTreeSet<AttributeId>,
+    currencies: BTreeMap<AttributeId, Currency>,
+    storage_code_id: CodeId,
+    storages: BTreeMap<u8, ActorId>,
     transaction_id: TransactionId,
-    transactions: BTreeMap<TamagotchiId, (TransactionId, AttributeId)>,
+    transactions: BTreeMap<TamagotchiId, Transaction>,
+    bundle_discount: u8,
+    revenue: BTreeMap<Currency, Price>,
+    paused: bool,
+    purchases: VecDeque<Purchase>,
+    sales: BTreeMap<AttributeId, Sales>,
+    registrar: Option<ActorId>,
+    registered: BTreeSet<TamagotchiId>,
+    curves: BTreeMap<AttributeId, PriceCurve>,
+    premiums: BTreeMap<AttributeId, (Price, u32)>,
+    coupons: BTreeMap<String, Coupon>,
+    listener: Option<ActorId>,
+    reserved: BTreeMap<TamagotchiId, BTreeSet<AttributeId>>,
+    pending_replies: BTreeMap<MessageId, TamagotchiId>,
 }
 
 impl AttributeStore {
@@ -29,161 +67,1347 @@ impl AttributeStore {
         attribute_id: AttributeId,
         metadata: &AttrMetadata,
         price: Price,
-    ) {
-        assert_eq!(msg::source(), self.admin, "Only admin can add attributes");
+    ) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::CatalogManager)?;
+        self.check_not_paused()?;
 
-        if self
-            .attributes
-            .insert(attribute_id, (metadata.clone(), price))
-            .is_some()
-        {
-            panic!("Attribute with that ID already exists");
+        if self.attributes.contains_key(&attribute_id) {
+            return Err(StoreError::AttributeAlreadyExists { attribute_id });
         }
+        check_metadata(attribute_id, metadata)?;
+        self.attributes
+            .insert(attribute_id, (metadata.clone(), price));
+        self.notify(StoreEvent::AttributeCreated { attribute_id });
 
-        msg::reply(StoreEvent::AttributeCreated { attribute_id }, 0)
-            .expect("Error in sending a reply `StoreEvent::AttributeCreated");
+        Ok(StoreEvent::AttributeCreated { attribute_id })
     }
-    async fn buy_attribute(&mut self, attribute_id: AttributeId) {
-        let (transaction_id, attribute_id) = if let Some((transaction_id, prev_attribute_id)) =
-            self.transactions.get(&msg::source())
-        {
-            // if `prev_attribute_id` is not equal to `attribute_id` then it means that transaction didn`t completed
-            // we ask the tamagotchi contract to complete the previous transaction
-            if attribute_id != *prev_attribute_id {
-                msg::reply(
-                    StoreEvent::CompletePrevTx {
-                        attribute_id: *prev_attribute_id,
-                    },
-                    0,
-                )
-                .expect("Error in sending a reply `StoreEvent::CompletePrevTx`");
-                return;
-            }
-            (*transaction_id, *prev_attribute_id)
-        } else {
-            let current_transaction_id = self.transaction_id;
-            self.transaction_id = self.transaction_id.wrapping_add(1);
-            self.transactions
-                .insert(msg::source(), (current_transaction_id, attribute_id));
-            (current_transaction_id, attribute_id)
-        };
 
-        let result = self.sell_attribute(transaction_id, attribute_id).await;
-        self.transactions.remove(&msg::source());
+    fn create_attributes(
+        &mut self,
+        attributes: Vec<(AttributeId, AttrMetadata, Price)>,
+    ) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::CatalogManager)?;
+        self.check_not_paused()?;
 
-        msg::reply(StoreEvent::AttributeSold { success: result }, 0)
-            .expect("Error in sending a reply `StoreEvent::AttributeSold`");
+        // nothing is inserted until the whole batch is known to be valid
+        let mut attribute_ids = BTreeSet::new();
+        for (attribute_id, metadata, _) in &attributes {
+            if self.attributes.contains_key(attribute_id) {
+                return Err(StoreError::AttributeAlreadyExists {
+                    attribute_id: *attribute_id,
+                });
+            }
+            if !attribute_ids.insert(*attribute_id) {
+                return Err(StoreError::DuplicateAttributes);
+            }
+            check_metadata(*attribute_id, metadata)?;
+        }
+
+        let attribute_ids = attributes
+            .into_iter()
+            .map(|(attribute_id, metadata, price)| {
+                self.attributes.insert(attribute_id, (metadata, price));
+                attribute_id
+            })
+            .collect::<Vec<_>>();
+        self.notify(StoreEvent::AttributesCreated {
+            attribute_ids: attribute_ids.clone(),
+        });
+
+        Ok(StoreEvent::AttributesCreated { attribute_ids })
     }
 
-    async fn sell_attribute(
+    fn update_attribute(
         &mut self,
-        transaction_id: TransactionId,
         attribute_id: AttributeId,
-    ) -> bool {
-        let (_, price) = self
+        metadata: &AttrMetadata,
+    ) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::CatalogManager)?;
+        check_metadata(attribute_id, metadata)?;
+
+        let (attr_metadata, _) = self
             .attributes
-            .get(&attribute_id)
-            .expect("Can`t get attribute_id");
+            .get_mut(&attribute_id)
+            .ok_or(StoreError::AttributeNotFound { attribute_id })?;
+        *attr_metadata = metadata.clone();
 
-        if transfer_tokens(
-            transaction_id,
-            &self.ft_contract_id,
-            &msg::source(),
-            &exec::program_id(),
-            *price,
-        )
-        .await
-        .is_ok()
+        Ok(StoreEvent::AttributeUpdated { attribute_id })
+    }
+
+    fn set_price(
+        &mut self,
+        attribute_id: AttributeId,
+        price: Price,
+    ) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::CatalogManager)?;
+
+        let (_, attr_price) = self
+            .attributes
+            .get_mut(&attribute_id)
+            .ok_or(StoreError::AttributeNotFound { attribute_id })?;
+        *attr_price = price;
+
+        Ok(StoreEvent::PriceSet {
+            attribute_id,
+            price,
+        })
+    }
+
+    fn retire_attribute(&mut self, attribute_id: AttributeId) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::CatalogManager)?;
+        self.check_exists(attribute_id)?;
+
+        // retired attributes stay in `attributes` so their owners can still resolve the metadata
+        self.retired.insert(attribute_id);
+
+        Ok(StoreEvent::AttributeRetired { attribute_id })
+    }
+
+    fn set_price_curve(
+        &mut self,
+        attribute_id: AttributeId,
+        curve: Option<PriceCurve>,
+    ) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::CatalogManager)?;
+        self.check_exists(attribute_id)?;
+
+        match curve {
+            Some(curve) => {
+                self.curves.insert(attribute_id, curve);
+            }
+            None => {
+                self.curves.remove(&attribute_id);
+                self.premiums.remove(&attribute_id);
+            }
+        }
+
+        Ok(StoreEvent::PriceCurveSet {
+            attribute_id,
+            curve,
+        })
+    }
+
+    fn get_price(&self, attribute_id: AttributeId) -> Result<StoreEvent, StoreError> {
+        self.check_exists(attribute_id)?;
+        Ok(StoreEvent::Price {
+            attribute_id,
+            price: self.current_price(attribute_id),
+        })
+    }
+
+    async fn buy_attribute(
+        &mut self,
+        attribute_id: AttributeId,
+        max_price: Option<Price>,
+        coupon: Option<String>,
+    ) -> Result<(StoreEvent, u128), StoreError> {
+        let (state, change) = self
+            .purchase(vec![attribute_id], msg::source(), max_price, coupon)
+            .await?;
+        Ok((StoreEvent::AttributeSold { state, change }, change))
+    }
+
+    async fn buy_attributes(
+        &mut self,
+        attribute_ids: Vec<AttributeId>,
+    ) -> Result<(StoreEvent, u128), StoreError> {
+        if attribute_ids.is_empty() {
+            return Err(StoreError::EmptyBundle);
+        }
+        if attribute_ids.iter().collect::<BTreeSet<_>>().len() != attribute_ids.len() {
+            return Err(StoreError::DuplicateAttributes);
+        }
+
+        let (state, change) = self
+            .purchase(attribute_ids, msg::source(), None, None)
+            .await?;
+        Ok((StoreEvent::AttributesSold { state, change }, change))
+    }
+
+    async fn buy_attribute_for(
+        &mut self,
+        attribute_id: AttributeId,
+        recipient: TamagotchiId,
+    ) -> Result<(StoreEvent, u128), StoreError> {
+        let (state, change) = self
+            .purchase(vec![attribute_id], recipient, None, None)
+            .await?;
+        Ok((
+            StoreEvent::AttributeSoldFor {
+                attribute_id,
+                payer: msg::source(),
+                recipient,
+                state,
+                change,
+            },
+            change,
+        ))
+    }
+
+    /// Sells `attribute_ids` to `recipient` on behalf of the message source.
+    ///
+    /// Returns the state the transaction ended in and the value to refund.
+    async fn purchase(
+        &mut self,
+        attribute_ids: Vec<AttributeId>,
+        recipient: TamagotchiId,
+        max_price: Option<Price>,
+        coupon: Option<String>,
+    ) -> Result<(TxState, u128), StoreError> {
+        let (transaction_id, charged) =
+            match self.resume_transaction(&attribute_ids, recipient, max_price)? {
+                Some(transaction_id) => (transaction_id, 0),
+                None => {
+                    self.open_transaction(attribute_ids, recipient, max_price, coupon)
+                        .await?
+                }
+            };
+        let source = msg::source();
+        if self.state_of(&source, transaction_id) == Some(TxState::Pending) {
+            self.pay(transaction_id).await;
+        }
+        if self.state_of(&source, transaction_id) == Some(TxState::Paid) {
+            self.deliver(&source, transaction_id).await;
+        }
+        // the payment is taken, so the store grants the attributes even if the buyer never retries
+        if self.state_of(&source, transaction_id) == Some(TxState::Paid) {
+            msg::send_delayed(
+                exec::program_id(),
+                StoreAction::DeliverTx { buyer: source },
+                0,
+                REDELIVERY_DELAY,
+            )
+            .expect("Error in sending a message `StoreAction::DeliverTx`");
+        }
+        // a finished transaction is dropped as soon as the buyer gets its outcome
+        let state = match self.state_of(&source, transaction_id) {
+            Some(state @ (TxState::Completed | TxState::Failed)) => {
+                self.transactions.remove(&source);
+                state
+            }
+            Some(state) => state,
+            // a concurrent retry of the same purchase finished it first
+            None => TxState::Completed,
+        };
+        // only the value paid for a new native purchase is kept
+        Ok((state, msg::value() - charged))
+    }
+
+    /// Returns the ID of the source`s open transaction if it is retried with the same request.
+    fn resume_transaction(
+        &mut self,
+        attribute_ids: &[AttributeId],
+        recipient: TamagotchiId,
+        max_price: Option<Price>,
+    ) -> Result<Option<TransactionId>, StoreError> {
+        // a completed purchase is only kept if its reply was lost, so the same request is its retry
+        let is_unanswered = |transaction: &Transaction| {
+            transaction.state == TxState::Completed
+                && transaction.attribute_ids == attribute_ids
+                && transaction.recipient == recipient
+        };
+        let Some(transaction) = self
+            .transactions
+            .get_mut(&msg::source())
+            .filter(|transaction| is_open(transaction) || is_unanswered(transaction))
+        else {
+            return Ok(None);
+        };
+
+        // if the pending purchase is not equal to the requested one then it means that transaction didn`t completed
+        // we ask the tamagotchi contract to complete the previous transaction
+        if transaction.attribute_ids != attribute_ids || transaction.recipient != recipient {
+            return Err(
+                if let &[attribute_id] = transaction.attribute_ids.as_slice() {
+                    StoreError::CompletePrevTx { attribute_id }
+                } else {
+                    StoreError::CompletePrevBundleTx {
+                        attribute_ids: transaction.attribute_ids.clone(),
+                    }
+                },
+            );
+        }
+        // the retry pays the quoted prices with the coupon that was redeemed for them
+        check_max_price(&transaction.prices, max_price)?;
+        transaction.message_id = msg::id();
+        Ok(Some(transaction.id))
+    }
+
+    /// Starts a new transaction of the source and returns its ID and the attached value taken for it.
+    async fn open_transaction(
+        &mut self,
+        attribute_ids: Vec<AttributeId>,
+        recipient: TamagotchiId,
+        max_price: Option<Price>,
+        coupon: Option<String>,
+    ) -> Result<(TransactionId, u128), StoreError> {
+        // a retry must still deliver what was paid for, so only new purchases are stopped
+        self.check_not_paused()?;
+        if self.registrar.is_some() && !self.registered.contains(&recipient) {
+            return Err(StoreError::UnknownTamagotchi {
+                tamagotchi_id: recipient,
+            });
+        }
+        let currency = self.currency_of(&attribute_ids)?;
+
+        // a finished or expired transaction is dropped and the purchase starts over with a new ID
+        self.drop_transaction(&msg::source());
+
+        // the ownership is read only after the reservation, so that a purchase finished meanwhile
+        // is either still reserved or already granted
+        self.reserve(recipient, &attribute_ids)?;
+        let owned = self.attributes_of(&recipient).await;
+        let result = self.start_transaction(
+            &attribute_ids,
+            recipient,
+            &owned,
+            currency,
+            max_price,
+            coupon,
+        );
+        if result.is_err() {
+            self.release(&recipient, &attribute_ids);
+        }
+        result
+    }
+
+    fn start_transaction(
+        &mut self,
+        attribute_ids: &[AttributeId],
+        recipient: TamagotchiId,
+        owned: &BTreeMap<AttributeId, Quantity>,
+        currency: Currency,
+        max_price: Option<Price>,
+        coupon: Option<String>,
+    ) -> Result<(TransactionId, u128), StoreError> {
+        self.check_for_sale(attribute_ids, owned)?;
+        let prices = self.quote(attribute_ids, coupon.as_deref())?;
+        check_max_price(&prices, max_price)?;
+        // the native value comes with the purchase, so such a transaction starts out paid
+        let (state, charged) = if currency == Currency::Native {
+            let price: Price = prices.iter().sum();
+            let value = msg::value();
+            if value < price {
+                return Err(StoreError::InsufficientValue { price, value });
+            }
+            (TxState::Paid, price)
+        } else {
+            (TxState::Pending, 0)
+        };
+        if let Some(code) = &coupon {
+            self.redeem_coupon(code);
+        }
+        let transaction = Transaction {
+            id: self.transaction_id,
+            attribute_ids: attribute_ids.to_vec(),
+            recipient,
+            prices,
+            coupon,
+            currency,
+            created_at: exec::block_height(),
+            state,
+            message_id: msg::id(),
+            transfer_id: None,
+            grant_id: None,
+        };
+        let transaction_id = transaction.id;
+        self.transaction_id = self.transaction_id.wrapping_add(1);
+        self.transactions.insert(msg::source(), transaction);
+        Ok((transaction_id, charged))
+    }
+
+    /// Reserves the permanent attributes for the recipient until the purchase buying them is finished,
+    /// so that no other purchase is charged for them meanwhile.
+    fn reserve(
+        &mut self,
+        recipient: TamagotchiId,
+        attribute_ids: &[AttributeId],
+    ) -> Result<(), StoreError> {
+        let permanent = self.permanent_of(attribute_ids);
+        if permanent.is_empty() {
+            return Ok(());
+        }
+
+        let reserved = self.reserved.entry(recipient).or_default();
+        if let Some(&attribute_id) = permanent
+            .iter()
+            .find(|attribute_id| reserved.contains(attribute_id))
         {
-            self.owners
-                .entry(msg::source())
-                .and_modify(|attributes| {
-                    attributes.insert(attribute_id);
-                })
-                .or_insert_with(|| [attribute_id].into());
-            return true;
+            return Err(StoreError::PurchaseInProgress { attribute_id });
         }
-        false
+        reserved.extend(permanent);
+        Ok(())
     }
 
-    fn get_attributes(&self, tmg_id: &TamagotchiId) {
-        let attributes = self.owners.get(tmg_id).unwrap_or(&BTreeSet::new()).clone();
-        msg::reply(StoreEvent::Attributes { attributes }, 0)
-            .expect("Error in sending a reply `StoreEvent::Attributes`");
+    /// Returns the attributes that can be owned only once.
+    fn permanent_of(&self, attribute_ids: &[AttributeId]) -> Vec<AttributeId> {
+        attribute_ids
+            .iter()
+            .copied()
+            .filter(|attribute_id| {
+                self.attributes
+                    .get(attribute_id)
+                    .is_some_and(|(attr_metadata, _)| !attr_metadata.category.is_consumable())
+            })
+            .collect()
     }
 
-    fn set_ft_contract_id(&mut self, ft_contract_id: &ActorId) {
-        assert_eq!(
-            msg::source(),
-            self.admin,
-            "Only admin can set fungible token contract"
+    fn release(&mut self, recipient: &TamagotchiId, attribute_ids: &[AttributeId]) {
+        let Some(reserved) = self.reserved.get_mut(recipient) else {
+            return;
+        };
+        for attribute_id in attribute_ids {
+            reserved.remove(attribute_id);
+        }
+        if reserved.is_empty() {
+            self.reserved.remove(recipient);
+        }
+    }
+
+    fn state_of(&self, buyer: &ActorId, transaction_id: TransactionId) -> Option<TxState> {
+        self.transactions
+            .get(buyer)
+            .filter(|transaction| transaction.id == transaction_id)
+            .map(|transaction| transaction.state)
+    }
+
+    /// Transfers the tokens for the source`s transaction.
+    ///
+    /// The outcome is recorded by `handle_reply` as soon as the token contract replies, so it
+    /// survives even if this message runs out of gas before resuming.
+    async fn pay(&mut self, transaction_id: TransactionId) {
+        let source = msg::source();
+        let transaction = &self.transactions[&source];
+        let price: Price = transaction.prices.iter().sum();
+
+        let reply = send_transfer(
+            transaction_id,
+            &self.ft_contract_of(transaction.currency),
+            &source,
+            &exec::program_id(),
+            price,
         );
-        self.ft_contract_id = *ft_contract_id;
-        msg::reply(
-            StoreEvent::FtContractIdSet {
-                ft_contract_id: *ft_contract_id,
+        if let Some(transaction) = self.transactions.get_mut(&source) {
+            transaction.transfer_id = Some(reply.waiting_reply_to);
+            self.pending_replies.insert(reply.waiting_reply_to, source);
+        }
+        let paid = matches!(reply.await, Ok(FTokenEvent::Ok));
+
+        // only takes effect if `handle_reply` didn`t have enough gas to record the outcome
+        self.settle_payment(&source, transaction_id, paid);
+    }
+
+    /// Moves a pending transaction to `Paid` or `Failed`.
+    fn settle_payment(&mut self, buyer: &ActorId, transaction_id: TransactionId, paid: bool) {
+        let Some(transaction) = self.transactions.get_mut(buyer).filter(|transaction| {
+            transaction.id == transaction_id && transaction.state == TxState::Pending
+        }) else {
+            return;
+        };
+
+        if paid {
+            transaction.state = TxState::Paid;
+            return;
+        }
+        transaction.state = TxState::Failed;
+        let transaction = transaction.clone();
+        // the buyer wasn`t charged, so the coupon can be redeemed again
+        self.restore_coupon(transaction.coupon.as_deref());
+        self.release(&transaction.recipient, &transaction.attribute_ids);
+    }
+
+    /// Removes the buyer`s transaction, giving back the coupon use and the reserved attributes
+    /// if it surely wasn`t paid.
+    fn drop_transaction(&mut self, buyer: &ActorId) {
+        let Some(transaction) = self.transactions.remove(buyer) else {
+            return;
+        };
+        if transaction.state == TxState::Pending && transaction.transfer_id.is_none() {
+            self.restore_coupon(transaction.coupon.as_deref());
+            self.release(&transaction.recipient, &transaction.attribute_ids);
+        }
+        self.notify(StoreEvent::TxRemoved {
+            tamagotchi_id: *buyer,
+        });
+    }
+
+    /// Grants the attributes of the buyer`s paid transaction.
+    ///
+    /// The transaction is completed by `handle_reply` once the storage confirms the grant, and
+    /// stays paid if the grant fails, so that a retry or `DeliverTx` grants the attributes again.
+    async fn deliver(&mut self, buyer: &ActorId, transaction_id: TransactionId) {
+        let recipient = self.transactions[buyer].recipient;
+        let permanent = self.permanent_of(&self.transactions[buyer].attribute_ids);
+        let Some(storage) = self.storage_of(&recipient).await else {
+            return;
+        };
+
+        // nothing is sent while the previous grant is still awaiting its reply
+        let Some(transaction) = self.transactions.get_mut(buyer).filter(|transaction| {
+            transaction.id == transaction_id
+                && transaction.state == TxState::Paid
+                && transaction.grant_id.is_none()
+        }) else {
+            return;
+        };
+        // all attributes of the bundle are granted at once after a single payment
+        let reply = msg::send_for_reply_as::<_, StorageEvent>(
+            storage,
+            StorageAction::Grant {
+                tamagotchi_id: recipient,
+                attribute_ids: transaction.attribute_ids.clone(),
+                permanent,
             },
             0,
+            REPLY_DEPOSIT,
         )
-        .expect("Error in sending a reply `StoreEvent::FtContractIdSet`");
+        .expect("Error in sending a message `StorageAction::Grant`");
+        let grant_id = reply.waiting_reply_to;
+        transaction.grant_id = Some(grant_id);
+        self.pending_replies.insert(grant_id, *buyer);
+        let granted = matches!(reply.await, Ok(StorageEvent::Granted));
+
+        // only takes effect if `handle_reply` didn`t have enough gas to record the outcome
+        self.settle_grant(buyer, grant_id, granted);
+    }
+
+    /// Grants the attributes of the buyer`s paid transaction on behalf of the buyer,
+    /// as the grant doesn`t need anything from them.
+    async fn deliver_tx(&mut self, buyer: ActorId) -> Result<StoreEvent, StoreError> {
+        let transaction_id = self
+            .transactions
+            .get(&buyer)
+            .filter(|transaction| transaction.state == TxState::Paid)
+            .map(|transaction| transaction.id)
+            .ok_or(StoreError::NoPaidTx)?;
+
+        self.deliver(&buyer, transaction_id).await;
+        // the completed transaction is kept, so the buyer`s retry gets the outcome without paying
+        Ok(StoreEvent::TxDelivered {
+            buyer,
+            transaction_id,
+            granted: self.state_of(&buyer, transaction_id) != Some(TxState::Paid),
+        })
+    }
+
+    /// Completes a paid transaction once its attributes are granted, or lets a retry grant them again.
+    fn settle_grant(&mut self, buyer: &ActorId, grant_id: MessageId, granted: bool) {
+        let Some(transaction) = self.transactions.get_mut(buyer).filter(|transaction| {
+            transaction.state == TxState::Paid && transaction.grant_id == Some(grant_id)
+        }) else {
+            return;
+        };
+
+        if !granted {
+            transaction.grant_id = None;
+            return;
+        }
+        transaction.state = TxState::Completed;
+        let transaction = transaction.clone();
+        // the storage holds the granted attributes from now on
+        self.release(&transaction.recipient, &transaction.attribute_ids);
+        self.record_sale(buyer, &transaction);
     }
 
-    fn remove_tx(&mut self, tmg_id: &TamagotchiId) {
-        assert_eq!(
-            msg::source(),
-            self.admin,
-            "Only admin can set remove transactions"
+    /// Records the outcome of a token transfer sent by `pay` or of a grant sent by `deliver`.
+    fn handle_purchase_reply(&mut self) {
+        let reply_to = msg::reply_to().expect("Unable to get the replied message ID");
+        // every message awaiting a reply gets exactly one, so its entry is removed here
+        let Some(buyer) = self.pending_replies.remove(&reply_to) else {
+            return;
+        };
+        let Some(transaction) = self.transactions.get(&buyer) else {
+            return;
+        };
+        let (transaction_id, transfer_id, grant_id) = (
+            transaction.id,
+            transaction.transfer_id,
+            transaction.grant_id,
         );
-        self.transactions.remove(tmg_id);
-        msg::reply(
-            StoreEvent::TxRemoved {
-                tamagotchi_id: *tmg_id,
+
+        if transfer_id == Some(reply_to) {
+            let paid = matches!(msg::load::<FTokenEvent>(), Ok(FTokenEvent::Ok));
+            self.settle_payment(&buyer, transaction_id, paid);
+        } else if grant_id == Some(reply_to) {
+            let granted = matches!(msg::load::<StorageEvent>(), Ok(StorageEvent::Granted));
+            self.settle_grant(&buyer, reply_to, granted);
+        }
+    }
+
+    /// Reports a purchase message that failed midway, so it can be retried,
+    /// and finishes the delivery of its transaction if it was already paid.
+    fn handle_failed_message(&self) {
+        let Ok(message_id) = msg::signal_from() else {
+            return;
+        };
+        let Some((buyer, transaction)) = self
+            .transactions
+            .iter()
+            .find(|(_, transaction)| transaction.message_id == message_id)
+        else {
+            return;
+        };
+
+        if transaction.state == TxState::Paid {
+            msg::send(
+                exec::program_id(),
+                StoreAction::DeliverTx { buyer: *buyer },
+                0,
+            )
+            .expect("Error in sending a message `StoreAction::DeliverTx`");
+        }
+        // the listener decodes all its messages as `StoreEvent`
+        self.notify(StoreEvent::TxInterrupted {
+            buyer: *buyer,
+            transaction_id: transaction.id,
+            state: transaction.state,
+        });
+    }
+
+    /// Rejects the purchase before any tokens are moved, so the buyer is never charged for nothing.
+    fn check_for_sale(
+        &self,
+        attribute_ids: &[AttributeId],
+        owned: &BTreeMap<AttributeId, Quantity>,
+    ) -> Result<(), StoreError> {
+        for &attribute_id in attribute_ids {
+            let (attr_metadata, _) = self
+                .attributes
+                .get(&attribute_id)
+                .ok_or(StoreError::AttributeNotFound { attribute_id })?;
+            if self.retired.contains(&attribute_id) {
+                return Err(StoreError::AttributeRetired { attribute_id });
+            }
+            // consumables stack, so only permanent attributes can`t be bought twice
+            if !attr_metadata.category.is_consumable() && owned.contains_key(&attribute_id) {
+                return Err(StoreError::AlreadyOwned { attribute_id });
+            }
+        }
+        Ok(())
+    }
+
+    async fn attributes_of(&self, tamagotchi_id: &TamagotchiId) -> BTreeMap<AttributeId, Quantity> {
+        let Some(storage) = self.storages.get(&shard_of(tamagotchi_id)) else {
+            return BTreeMap::new();
+        };
+        match request_storage(
+            *storage,
+            StorageAction::GetAttributes {
+                tamagotchi_id: *tamagotchi_id,
             },
+        )
+        .await
+        {
+            StorageEvent::Attributes { attributes } => attributes,
+            _ => unreachable!("Storage replies to `GetAttributes` with `Attributes`"),
+        }
+    }
+
+    /// Returns the storage holding the shard of the tamagotchi, creating it on first use.
+    ///
+    /// Returns `None` if a new storage fails to initialize, so the grant is retried later.
+    async fn storage_of(&mut self, tamagotchi_id: &TamagotchiId) -> Option<ActorId> {
+        let shard = shard_of(tamagotchi_id);
+        if let Some(storage) = self.storages.get(&shard) {
+            return Some(*storage);
+        }
+
+        let (storage, _) = ProgramGenerator::create_program_with_gas_for_reply(
+            self.storage_code_id,
+            (),
+            STORAGE_GAS,
             0,
+            // the storage doesn`t reply to its initialization, so the reply carries no gas of its own
+            REPLY_DEPOSIT,
+        )
+        .expect("Error in creating a storage program")
+        .await
+        .ok()?;
+        // another purchase may have created the storage while this one was waiting
+        Some(*self.storages.entry(shard).or_insert(storage))
+    }
+
+    fn currency_of(&self, attribute_ids: &[AttributeId]) -> Result<Currency, StoreError> {
+        let currency = |attribute_id: &AttributeId| {
+            self.currencies
+                .get(attribute_id)
+                .copied()
+                .unwrap_or_default()
+        };
+        let first = currency(&attribute_ids[0]);
+        if !attribute_ids.iter().all(|id| currency(id) == first) {
+            return Err(StoreError::MixedCurrencies);
+        }
+        Ok(first)
+    }
+
+    fn ft_contract_of(&self, currency: Currency) -> ActorId {
+        match currency {
+            Currency::FungibleToken => self.ft_contract_id,
+            Currency::Token(ft_contract_id) => ft_contract_id,
+            Currency::Native => unreachable!("Native value isn`t a fungible token"),
+        }
+    }
+
+    fn record_sale(&mut self, buyer: &ActorId, transaction: &Transaction) {
+        let revenue = self.revenue.entry(transaction.currency).or_default();
+        *revenue = revenue.saturating_add(transaction.prices.iter().sum());
+
+        let mut purchases = Vec::with_capacity(transaction.attribute_ids.len());
+        for (attribute_id, price) in transaction.attribute_ids.iter().zip(&transaction.prices) {
+            let sales = self.sales.entry(*attribute_id).or_default();
+            sales.units_sold += 1;
+            sales.revenue = sales.revenue.saturating_add(*price);
+            self.raise_premium(*attribute_id);
+
+            if self.purchases.len() == MAX_PURCHASES {
+                self.purchases.pop_front();
+            }
+            let purchase = Purchase {
+                buyer: *buyer,
+                recipient: transaction.recipient,
+                attribute_id: *attribute_id,
+                price: *price,
+                currency: transaction.currency,
+                transaction_id: transaction.id,
+                block: exec::block_height(),
+            };
+            self.purchases.push_back(purchase.clone());
+            purchases.push(purchase);
+        }
+        self.notify(StoreEvent::Purchases { purchases });
+    }
+
+    /// Returns the price of each attribute, with the bundle discount applied if there are several.
+    ///
+    /// The attributes must have been checked to exist with `check_for_sale`.
+    fn prices_of(&self, attribute_ids: &[AttributeId]) -> Vec<Price> {
+        let discount = if attribute_ids.len() > 1 {
+            self.bundle_discount
+        } else {
+            0
+        };
+        attribute_ids
+            .iter()
+            .map(|attribute_id| discounted(self.current_price(*attribute_id), discount))
+            .collect()
+    }
+
+    /// Returns the prices to pay, with the coupon applied to the attribute it`s redeemed for.
+    ///
+    /// Coupons are only accepted by `BuyAttribute`, so there is a single attribute to discount.
+    fn quote(
+        &self,
+        attribute_ids: &[AttributeId],
+        coupon: Option<&str>,
+    ) -> Result<Vec<Price>, StoreError> {
+        let mut prices = self.prices_of(attribute_ids);
+        let Some(code) = coupon else {
+            return Ok(prices);
+        };
+
+        let coupon = self.coupons.get(code).ok_or(StoreError::CouponNotFound)?;
+        if exec::block_height() >= coupon.expires_at {
+            return Err(StoreError::CouponExpired);
+        }
+        if coupon.uses_left == 0 {
+            return Err(StoreError::CouponExhausted);
+        }
+        if coupon
+            .attribute_id
+            .is_some_and(|attribute_id| attribute_id != attribute_ids[0])
+        {
+            return Err(StoreError::CouponNotApplicable);
+        }
+
+        let price = &mut prices[0];
+        *price = match coupon.discount {
+            Discount::Percent(percent) => discounted(*price, percent),
+            Discount::Fixed(amount) => price.saturating_sub(amount),
+        };
+        Ok(prices)
+    }
+
+    fn redeem_coupon(&mut self, code: &str) {
+        if let Some(coupon) = self.coupons.get_mut(code) {
+            coupon.uses_left -= 1;
+        }
+    }
+
+    fn restore_coupon(&mut self, code: Option<&str>) {
+        if let Some(coupon) = code.and_then(|code| self.coupons.get_mut(code)) {
+            coupon.uses_left = coupon.uses_left.saturating_add(1);
+        }
+    }
+
+    /// Returns the base price plus what is left of the curve premium after the decay.
+    fn current_price(&self, attribute_id: AttributeId) -> Price {
+        let (_, price) = self
+            .attributes
+            .get(&attribute_id)
+            .expect("Can`t get attribute_id");
+        price.saturating_add(self.current_premium(attribute_id))
+    }
+
+    fn current_premium(&self, attribute_id: AttributeId) -> Price {
+        let (Some(curve), Some((premium, raised_at))) = (
+            self.curves.get(&attribute_id),
+            self.premiums.get(&attribute_id),
+        ) else {
+            return 0;
+        };
+        let blocks = exec::block_height().saturating_sub(*raised_at);
+        premium.saturating_sub(curve.decay_per_block.saturating_mul(blocks.into()))
+    }
+
+    fn raise_premium(&mut self, attribute_id: AttributeId) {
+        let Some(curve) = self.curves.get(&attribute_id) else {
+            return;
+        };
+        let premium = self
+            .current_premium(attribute_id)
+            .saturating_add(curve.step);
+        self.premiums
+            .insert(attribute_id, (premium, exec::block_height()));
+    }
+
+    fn get_purchases(&self, offset: u32, limit: u32) -> Result<StoreEvent, StoreError> {
+        let purchases = self
+            .purchases
+            .iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect();
+        Ok(StoreEvent::Purchases { purchases })
+    }
+
+    async fn use_attribute(&mut self, attribute_id: AttributeId) -> Result<StoreEvent, StoreError> {
+        let (attr_metadata, _) = self
+            .attributes
+            .get(&attribute_id)
+            .ok_or(StoreError::AttributeNotFound { attribute_id })?;
+        let category = attr_metadata.category;
+        if !category.is_consumable() {
+            return Err(StoreError::NotConsumable { attribute_id });
+        }
+
+        let storage = *self
+            .storages
+            .get(&shard_of(&msg::source()))
+            .ok_or(StoreError::NotOwned { attribute_id })?;
+        let StorageEvent::Used { remaining } = request_storage(
+            storage,
+            StorageAction::Use {
+                tamagotchi_id: msg::source(),
+                attribute_id,
+            },
         )
-        .expect("Error in sending a reply `StoreEvent::TxRemoved`");
+        .await
+        else {
+            return Err(StoreError::NotOwned { attribute_id });
+        };
+
+        Ok(StoreEvent::AttributeUsed {
+            attribute_id,
+            category,
+            remaining,
+        })
+    }
+
+    /// Pages through the owners of all storages in the order of their shards.
+    async fn owners_of(
+        &self,
+        attribute_id: AttributeId,
+        mut offset: u32,
+        mut limit: u32,
+    ) -> Result<StoreEvent, StoreError> {
+        let mut owners = Vec::new();
+        for storage in self.storages.values() {
+            if limit == 0 {
+                break;
+            }
+            let StorageEvent::Owners {
+                owners: page,
+                total,
+            } = request_storage(
+                *storage,
+                StorageAction::OwnersOf {
+                    attribute_id,
+                    offset,
+                    limit,
+                },
+            )
+            .await
+            else {
+                unreachable!("Storage replies to `OwnersOf` with `Owners`");
+            };
+            offset = offset.saturating_sub(total);
+            limit -= page.len() as u32;
+            owners.extend(page);
+        }
+        Ok(StoreEvent::Owners {
+            attribute_id,
+            owners,
+        })
+    }
+
+    fn get_attribute(&self, attribute_id: AttributeId) -> Result<StoreEvent, StoreError> {
+        let (attr_metadata, _) = self
+            .attributes
+            .get(&attribute_id)
+            .ok_or(StoreError::AttributeNotFound { attribute_id })?;
+        Ok(StoreEvent::Attribute {
+            attr_metadata: attr_metadata.clone(),
+            price: self.current_price(attribute_id),
+        })
+    }
+
+    async fn get_attributes(&self, tmg_id: &TamagotchiId) -> Result<StoreEvent, StoreError> {
+        let attributes = self.attributes_of(tmg_id).await.into_keys().collect();
+        Ok(StoreEvent::Attributes { attributes })
+    }
+
+    fn set_ft_contract_id(&mut self, ft_contract_id: &ActorId) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::Treasurer)?;
+        self.ft_contract_id = *ft_contract_id;
+        Ok(StoreEvent::FtContractIdSet {
+            ft_contract_id: *ft_contract_id,
+        })
+    }
+
+    async fn withdraw(
+        &mut self,
+        currency: Currency,
+        to: ActorId,
+        amount: Price,
+    ) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::Treasurer)?;
+
+        let success = if currency == Currency::Native {
+            msg::send(to, (), amount).is_ok()
+        } else {
+            let transaction_id = self.transaction_id;
+            self.transaction_id = self.transaction_id.wrapping_add(1);
+
+            let reply = send_transfer(
+                transaction_id,
+                &self.ft_contract_of(currency),
+                &exec::program_id(),
+                &to,
+                amount,
+            );
+            matches!(reply.await, Ok(FTokenEvent::Ok))
+        };
+
+        Ok(StoreEvent::Withdrawn {
+            currency,
+            to,
+            amount,
+            success,
+        })
+    }
+
+    fn set_currency(
+        &mut self,
+        attribute_id: AttributeId,
+        currency: Currency,
+    ) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::CatalogManager)?;
+        self.check_exists(attribute_id)?;
+        self.currencies.insert(attribute_id, currency);
+        Ok(StoreEvent::CurrencySet {
+            attribute_id,
+            currency,
+        })
+    }
+
+    fn set_bundle_discount(&mut self, discount: u8) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::CatalogManager)?;
+        if discount > 100 {
+            return Err(StoreError::InvalidDiscount { discount });
+        }
+        self.bundle_discount = discount;
+        Ok(StoreEvent::BundleDiscountSet { discount })
+    }
+
+    fn create_coupon(&mut self, code: String, coupon: Coupon) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::CatalogManager)?;
+        if let Discount::Percent(discount) = coupon.discount {
+            if discount > 100 {
+                return Err(StoreError::InvalidDiscount { discount });
+            }
+        }
+        if let Some(attribute_id) = coupon.attribute_id {
+            self.check_exists(attribute_id)?;
+        }
+        if self.coupons.contains_key(&code) {
+            return Err(StoreError::CouponAlreadyExists);
+        }
+        self.coupons.insert(code.clone(), coupon);
+        Ok(StoreEvent::CouponCreated { code })
+    }
+
+    fn remove_coupon(&mut self, code: String) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::CatalogManager)?;
+        self.coupons
+            .remove(&code)
+            .ok_or(StoreError::CouponNotFound)?;
+        Ok(StoreEvent::CouponRemoved { code })
+    }
+
+    fn remove_tx(&mut self, tmg_id: &TamagotchiId) -> Result<StoreEvent, StoreError> {
+        self.check_role(Role::TxOperator)?;
+        if self
+            .transactions
+            .get(tmg_id)
+            .is_some_and(|transaction| !is_droppable(transaction))
+        {
+            return Err(StoreError::TxInProgress);
+        }
+        self.drop_transaction(tmg_id);
+        Ok(StoreEvent::TxRemoved {
+            tamagotchi_id: *tmg_id,
+        })
+    }
+
+    /// Only offers the admin rights, so a mistyped account can`t lock the store.
+    fn transfer_admin(&mut self, new_admin: ActorId) -> Result<StoreEvent, StoreError> {
+        self.check_admin()?;
+        self.pending_admin = Some(new_admin);
+        Ok(StoreEvent::AdminOffered { new_admin })
+    }
+
+    fn accept_admin(&mut self) -> Result<StoreEvent, StoreError> {
+        let new_admin = msg::source();
+        if self.pending_admin != Some(new_admin) {
+            return Err(StoreError::Unauthorized);
+        }
+        self.admin = new_admin;
+        self.pending_admin = None;
+        Ok(StoreEvent::AdminTransferred { new_admin })
+    }
+
+    fn grant_role(&mut self, account: ActorId, role: Role) -> Result<StoreEvent, StoreError> {
+        self.check_admin()?;
+        self.roles.entry(account).or_default().insert(role);
+        Ok(StoreEvent::RoleGranted { account, role })
+    }
+
+    fn revoke_role(&mut self, account: ActorId, role: Role) -> Result<StoreEvent, StoreError> {
+        self.check_admin()?;
+        if let Some(roles) = self.roles.get_mut(&account) {
+            roles.remove(&role);
+            if roles.is_empty() {
+                self.roles.remove(&account);
+            }
+        }
+        Ok(StoreEvent::RoleRevoked { account, role })
+    }
+
+    fn set_listener(&mut self, listener: Option<ActorId>) -> Result<StoreEvent, StoreError> {
+        self.check_admin()?;
+        self.listener = listener;
+        Ok(StoreEvent::ListenerSet { listener })
+    }
+
+    /// Forwards the event to the listener, so it can follow the store without reading the whole state.
+    fn notify(&self, event: StoreEvent) {
+        if let Some(listener) = self.listener {
+            msg::send(listener, event, 0).expect("Error in sending a message to the listener");
+        }
+    }
+
+    fn set_registrar(&mut self, registrar: Option<ActorId>) -> Result<StoreEvent, StoreError> {
+        self.check_admin()?;
+        self.registrar = registrar;
+        Ok(StoreEvent::RegistrarSet { registrar })
+    }
+
+    fn register_tamagotchi(
+        &mut self,
+        tamagotchi_id: TamagotchiId,
+    ) -> Result<StoreEvent, StoreError> {
+        let source = msg::source();
+        if source != self.admin && Some(source) != self.registrar {
+            return Err(StoreError::Unauthorized);
+        }
+        self.registered.insert(tamagotchi_id);
+        Ok(StoreEvent::TamagotchiRegistered { tamagotchi_id })
+    }
+
+    fn pause(&mut self) -> Result<StoreEvent, StoreError> {
+        self.check_admin()?;
+        self.paused = true;
+        Ok(StoreEvent::Paused)
+    }
+
+    fn unpause(&mut self) -> Result<StoreEvent, StoreError> {
+        self.check_admin()?;
+        self.paused = false;
+        Ok(StoreEvent::Unpaused)
+    }
+
+    fn cancel_tx(&mut self) -> Result<StoreEvent, StoreError> {
+        let transaction = self
+            .transactions
+            .get(&msg::source())
+            .filter(|transaction| transaction.state == TxState::Pending)
+            .ok_or(StoreError::NoPendingTx)?;
+        if !is_droppable(transaction) {
+            return Err(StoreError::TxInProgress);
+        }
+        if !is_expired(transaction) {
+            return Err(StoreError::TxNotExpired);
+        }
+
+        self.drop_transaction(&msg::source());
+        Ok(StoreEvent::TxRemoved {
+            tamagotchi_id: msg::source(),
+        })
+    }
+
+    fn check_not_paused(&self) -> Result<(), StoreError> {
+        if self.paused {
+            return Err(StoreError::Paused);
+        }
+        Ok(())
+    }
+
+    fn check_exists(&self, attribute_id: AttributeId) -> Result<(), StoreError> {
+        if !self.attributes.contains_key(&attribute_id) {
+            return Err(StoreError::AttributeNotFound { attribute_id });
+        }
+        Ok(())
+    }
+
+    fn check_admin(&self) -> Result<(), StoreError> {
+        if msg::source() != self.admin {
+            return Err(StoreError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// The admin holds every role implicitly.
+    fn check_role(&self, role: Role) -> Result<(), StoreError> {
+        let source = msg::source();
+        if source != self.admin
+            && !self
+                .roles
+                .get(&source)
+                .is_some_and(|roles| roles.contains(&role))
+        {
+            return Err(StoreError::Unauthorized);
+        }
+        Ok(())
+    }
+
+    /// Returns the event to reply with and the part of the attached value to return.
+    async fn handle(&mut self, action: StoreAction) -> Result<(StoreEvent, u128), StoreError> {
+        Ok(match action {
+            StoreAction::CreateAttribute {
+                attribute_id,
+                attr_metadata,
+                price,
+            } => (
+                self.create_attribute(attribute_id, &attr_metadata, price)?,
+                0,
+            ),
+            StoreAction::CreateAttributes { attributes } => {
+                (self.create_attributes(attributes)?, 0)
+            }
+            StoreAction::UpdateAttribute {
+                attribute_id,
+                attr_metadata,
+            } => (self.update_attribute(attribute_id, &attr_metadata)?, 0),
+            StoreAction::SetPrice {
+                attribute_id,
+                price,
+            } => (self.set_price(attribute_id, price)?, 0),
+            StoreAction::RetireAttribute { attribute_id } => {
+                (self.retire_attribute(attribute_id)?, 0)
+            }
+            StoreAction::SetPriceCurve {
+                attribute_id,
+                curve,
+            } => (self.set_price_curve(attribute_id, curve)?, 0),
+            StoreAction::GetPrice { attribute_id } => (self.get_price(attribute_id)?, 0),
+            StoreAction::SetCurrency {
+                attribute_id,
+                currency,
+            } => (self.set_currency(attribute_id, currency)?, 0),
+            StoreAction::BuyAttribute {
+                attribute_id,
+                max_price,
+                coupon,
+            } => self.buy_attribute(attribute_id, max_price, coupon).await?,
+            StoreAction::BuyAttributes { attribute_ids } => {
+                self.buy_attributes(attribute_ids).await?
+            }
+            StoreAction::BuyAttributeFor {
+                attribute_id,
+                recipient,
+            } => self.buy_attribute_for(attribute_id, recipient).await?,
+            StoreAction::UseAttribute { attribute_id } => {
+                (self.use_attribute(attribute_id).await?, 0)
+            }
+            StoreAction::GetAttribute { attribute_id } => (self.get_attribute(attribute_id)?, 0),
+            StoreAction::GetPurchases { offset, limit } => (self.get_purchases(offset, limit)?, 0),
+            StoreAction::OwnersOf {
+                attribute_id,
+                offset,
+                limit,
+            } => (self.owners_of(attribute_id, offset, limit).await?, 0),
+            StoreAction::GetAttributes { tamagotchi_id } => {
+                (self.get_attributes(&tamagotchi_id).await?, 0)
+            }
+            StoreAction::SetFtContractId { ft_contract_id } => {
+                (self.set_ft_contract_id(&ft_contract_id)?, 0)
+            }
+            StoreAction::Withdraw {
+                currency,
+                to,
+                amount,
+            } => (self.withdraw(currency, to, amount).await?, 0),
+            StoreAction::SetBundleDiscount { discount } => (self.set_bundle_discount(discount)?, 0),
+            StoreAction::CreateCoupon { code, coupon } => (self.create_coupon(code, coupon)?, 0),
+            StoreAction::RemoveCoupon { code } => (self.remove_coupon(code)?, 0),
+            StoreAction::RemoveTx { tamagotchi_id } => (self.remove_tx(&tamagotchi_id)?, 0),
+            StoreAction::CancelTx => (self.cancel_tx()?, 0),
+            StoreAction::DeliverTx { buyer } => (self.deliver_tx(buyer).await?, 0),
+            StoreAction::TransferAdmin { new_admin } => (self.transfer_admin(new_admin)?, 0),
+            StoreAction::AcceptAdmin => (self.accept_admin()?, 0),
+            StoreAction::GrantRole { account, role } => (self.grant_role(account, role)?, 0),
+            StoreAction::RevokeRole { account, role } => (self.revoke_role(account, role)?, 0),
+            StoreAction::SetRegistrar { registrar } => (self.set_registrar(registrar)?, 0),
+            StoreAction::SetListener { listener } => (self.set_listener(listener)?, 0),
+            StoreAction::RegisterTamagotchi { tamagotchi_id } => {
+                (self.register_tamagotchi(tamagotchi_id)?, 0)
+            }
+            StoreAction::Pause => (self.pause()?, 0),
+            StoreAction::Unpause => (self.unpause()?, 0),
+        })
     }
 }
 
-#[gstd::async_main]
+#[gstd::async_main(handle_reply = on_reply, handle_signal = on_signal)]
 async fn main() {
+    exec::system_reserve_gas(SIGNAL_GAS).expect("Unable to reserve gas for a signal");
     let action: StoreAction = msg::load().expect("Unable to decode `StoreAction");
     let store: &mut AttributeStore =
         unsafe { STORE.as_mut().expect("The contract is not initialized") };
-    match action {
-        StoreAction::CreateAttribute {
-            attribute_id,
-            attr_metadata,
-            price,
-        } => store.create_attribute(attribute_id, &attr_metadata, price),
-        StoreAction::BuyAttribute { attribute_id } => store.buy_attribute(attribute_id).await,
-        StoreAction::GetAttributes { tamagotchi_id } => store.get_attributes(&tamagotchi_id),
-        StoreAction::SetFtContractId { ft_contract_id } => {
-            store.set_ft_contract_id(&ft_contract_id)
-        }
-        StoreAction::RemoveTx { tamagotchi_id } => store.remove_tx(&tamagotchi_id),
+    let result = store.handle(action).await;
+
+    // a rejected action returns all the attached value, a successful purchase only the change
+    let (reply, value) = match result {
+        Ok((event, change)) => (Ok(event), change),
+        Err(error) => (Err(error), msg::value()),
+    };
+    if value > 0 {
+        store.notify(StoreEvent::Refunded {
+            to: msg::source(),
+            amount: value,
+        });
     }
+    msg::reply::<Result<StoreEvent, StoreError>>(reply, value)
+        .expect("Error in sending a reply `Result<StoreEvent, StoreError>`");
 }
 
 #[no_mangle]
 extern fn init() {
-    let ft_contract_id: ActorId = msg::load().expect("Unable to decode `ActorId`");
+    let InitStore {
+        ft_contract_id,
+        storage_code_id,
+    } = msg::load().expect("Unable to decode `InitStore`");
     let store = AttributeStore {
         admin: msg::source(),
         ft_contract_id,
+        storage_code_id,
         ..Default::default()
     };
     unsafe { STORE = Some(store) };
 }
 
-async fn transfer_tokens(
+/// Tamagotchis are split into 16 shards by the first hex digit of their ID.
+fn shard_of(tamagotchi_id: &TamagotchiId) -> u8 {
+    tamagotchi_id.as_ref()[0] >> 4
+}
+
+async fn request_storage(storage: ActorId, action: StorageAction) -> StorageEvent {
+    msg::send_for_reply_as::<_, StorageEvent>(storage, action, 0, 0)
+        .expect("Error in sending a message `StorageAction`")
+        .await
+        .expect("Unable to decode `StorageEvent`")
+}
+
+fn check_metadata(attribute_id: AttributeId, metadata: &AttrMetadata) -> Result<(), StoreError> {
+    metadata
+        .validate()
+        .map_err(|error| StoreError::InvalidMetadata {
+            attribute_id,
+            error,
+        })
+}
+
+/// Takes `percent` off the price, dividing first so that large prices can`t overflow.
+fn discounted(price: Price, percent: u8) -> Price {
+    let percent = Price::from(percent);
+    price - (price / 100 * percent + price % 100 * percent / 100)
+}
+
+fn check_max_price(prices: &[Price], max_price: Option<Price>) -> Result<(), StoreError> {
+    let price: Price = prices.iter().sum();
+    match max_price {
+        Some(max_price) if price > max_price => {
+            Err(StoreError::PriceExceedsMax { price, max_price })
+        }
+        _ => Ok(()),
+    }
+}
+
+fn on_reply() {
+    if let Some(store) = unsafe { STORE.as_mut() } {
+        store.handle_purchase_reply();
+    }
+}
+
+fn on_signal() {
+    if let Some(store) = unsafe { STORE.as_ref() } {
+        store.handle_failed_message();
+    }
+}
+
+fn is_expired(transaction: &Transaction) -> bool {
+    exec::block_height().saturating_sub(transaction.created_at) >= TX_LIFETIME
+}
+
+/// Returns whether a new purchase of the buyer must wait for the transaction to be finished.
+///
+/// Only a transaction that surely wasn`t paid can expire, as the buyer may still be owed the
+/// attributes otherwise.
+fn is_open(transaction: &Transaction) -> bool {
+    !is_droppable(transaction)
+        || (transaction.state == TxState::Pending && !is_expired(transaction))
+}
+
+/// Returns whether the transaction can be dropped without losing the tokens of the buyer.
+fn is_droppable(transaction: &Transaction) -> bool {
+    match transaction.state {
+        // once the transfer is sent, only its reply tells whether the tokens were moved
+        TxState::Pending => transaction.transfer_id.is_none(),
+        TxState::Paid => false,
+        TxState::Completed | TxState::Failed => true,
+    }
+}
+
+fn send_transfer(
     transaction_id: TransactionId,
     token_address: &ActorId,
     from: &ActorId,
     to: &ActorId,
     amount_tokens: u128,
-) -> Result<(), ()> {
-    let reply = msg::send_for_reply_as::<_, FTokenEvent>(
+) -> CodecMessageFuture<FTokenEvent> {
+    msg::send_for_reply_as::<_, FTokenEvent>(
         *token_address,
         FTokenAction::Message {
             transaction_id,
@@ -194,31 +1418,84 @@ async fn transfer_tokens(
             },
         },
         0,
-        0,
+        REPLY_DEPOSIT,
     )
     .expect("Error in sending a message `FTokenAction::Message`")
-    .await;
-
-    match reply {
-        Ok(FTokenEvent::Ok) => Ok(()),
-        _ => Err(()),
-    }
 }
 
 #[no_mangle]
 extern fn state() {
     let store = unsafe { STORE.as_ref().expect("The contract is not initialized") };
+    let query: StateQuery = msg::load().expect("Unable to decode `StateQuery`");
 
-    msg::reply(
-        store_io::AttributeStore {
+    let reply = match query {
+        StateQuery::Full => StateReply::Full(store_io::AttributeStore {
             admin: store.admin,
+            pending_admin: store.pending_admin,
+            roles: store.roles.clone(),
             ft_contract_id: store.ft_contract_id,
             attributes: store.attributes.clone(),
-            owners: store.owners.clone(),
+            retired: store.retired.clone(),
+            currencies: store.currencies.clone(),
+            storage_code_id: store.storage_code_id,
+            storages: store.storages.clone(),
             transaction_id: store.transaction_id,
             transactions: store.transactions.clone(),
+            bundle_discount: store.bundle_discount,
+            revenue: store.revenue.clone(),
+            paused: store.paused,
+            purchases: store.purchases.clone(),
+            sales: store.sales.clone(),
+            registrar: store.registrar,
+            registered: store.registered.clone(),
+            curves: store.curves.clone(),
+            premiums: store.premiums.clone(),
+            coupons: store.coupons.clone(),
+            listener: store.listener,
+            reserved: store.reserved.clone(),
+            pending_replies: store.pending_replies.clone(),
+        }),
+        StateQuery::Catalog { offset, limit } => StateReply::Catalog {
+            attributes: store
+                .attributes
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .map(|(attribute_id, (attr_metadata, _))| CatalogEntry {
+                    attribute_id: *attribute_id,
+                    attr_metadata: attr_metadata.clone(),
+                    price: store.current_price(*attribute_id),
+                    currency: store
+                        .currencies
+                        .get(attribute_id)
+                        .copied()
+                        .unwrap_or_default(),
+                    retired: store.retired.contains(attribute_id),
+                })
+                .collect(),
         },
-        0,
-    )
-    .expect("Failed to share state");
+        StateQuery::AttributesStorageOf { tamagotchi_id } => StateReply::AttributesStorageOf {
+            storage: store.storages.get(&shard_of(&tamagotchi_id)).copied(),
+        },
+        StateQuery::PendingTx { tamagotchi_id } => StateReply::PendingTx {
+            transaction: store
+                .transactions
+                .get(&tamagotchi_id)
+                .cloned()
+                .map(Box::new),
+        },
+        StateQuery::Purchases { offset, limit } => StateReply::Purchases {
+            purchases: store
+                .purchases
+                .iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .cloned()
+                .collect(),
+        },
+        StateQuery::Sales { attribute_id } => StateReply::Sales {
+            sales: store.sales.get(&attribute_id).cloned().unwrap_or_default(),
+        },
+    };
+    msg::reply(reply, 0).expect("Failed to share state");
 }
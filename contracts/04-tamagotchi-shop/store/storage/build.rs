@@ -0,0 +1,5 @@
+use store_storage_io::ProgramMetadata;
+
+fn main() {
+    gear_wasm_builder::build_with_metadata::<ProgramMetadata>();
+}
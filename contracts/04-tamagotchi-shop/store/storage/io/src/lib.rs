@@ -0,0 +1,99 @@
+#![no_std]
+
+use gmeta::{In, InOut, Metadata};
+use gstd::{
+    collections::{BTreeMap, BTreeSet},
+    prelude::*,
+    ActorId,
+};
+use store_io::{AttributeId, Quantity, TamagotchiId};
+
+pub struct ProgramMetadata;
+
+impl Metadata for ProgramMetadata {
+    type Init = In<()>;
+    type Handle = InOut<StorageAction, StorageEvent>;
+    type Reply = ();
+    type Others = ();
+    type Signal = ();
+    type State = InOut<StorageQuery, StorageReply>;
+}
+
+/// Ownership shard of the store holding the attributes of a part of tamagotchis.
+#[derive(Default, Encode, Decode, Clone, TypeInfo)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub struct StorageState {
+    /// The store that created the storage and is the only one allowed to use it.
+    pub store: ActorId,
+    /// Number of units of each attribute owned by a tamagotchi.
+    pub owners: BTreeMap<TamagotchiId, BTreeMap<AttributeId, Quantity>>,
+    /// Reverse index of `owners`: tamagotchis holding at least one unit of an attribute.
+    pub attribute_owners: BTreeMap<AttributeId, BTreeSet<TamagotchiId>>,
+}
+
+/// Selects the part of the state to read.
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum StorageQuery {
+    Full,
+    AttributesOf { tamagotchi_id: TamagotchiId },
+}
+
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum StorageReply {
+    Full(StorageState),
+    /// Number of units of each attribute owned by the tamagotchi.
+    AttributesOf {
+        attributes: BTreeMap<AttributeId, Quantity>,
+    },
+}
+
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum StorageAction {
+    /// Adds a unit of each attribute to the tamagotchi.
+    Grant {
+        tamagotchi_id: TamagotchiId,
+        attribute_ids: Vec<AttributeId>,
+        /// Attributes of `attribute_ids` that can be owned only once, so they aren`t added again
+        /// if the tamagotchi already has them.
+        permanent: Vec<AttributeId>,
+    },
+    /// Takes a unit of the attribute from the tamagotchi.
+    Use {
+        tamagotchi_id: TamagotchiId,
+        attribute_id: AttributeId,
+    },
+    GetAttributes {
+        tamagotchi_id: TamagotchiId,
+    },
+    OwnersOf {
+        attribute_id: AttributeId,
+        offset: u32,
+        limit: u32,
+    },
+}
+
+#[derive(Encode, Decode, TypeInfo)]
+#[codec(crate = gstd::codec)]
+#[scale_info(crate = gstd::scale_info)]
+pub enum StorageEvent {
+    Granted,
+    Used {
+        remaining: Quantity,
+    },
+    NotOwned,
+    Attributes {
+        attributes: BTreeMap<AttributeId, Quantity>,
+    },
+    /// A page of the owners held by the storage and the total number of them.
+    Owners {
+        owners: Vec<TamagotchiId>,
+        total: u32,
+    },
+}
@@ -0,0 +1,137 @@
+#![no_std]
+
+use gstd::{msg, prelude::*};
+use store_io::{AttributeId, TamagotchiId};
+use store_storage_io::{StorageAction, StorageEvent, StorageQuery, StorageReply, StorageState};
+
+static mut STORAGE: Option<StorageState> = None;
+
+fn grant(
+    storage: &mut StorageState,
+    tamagotchi_id: TamagotchiId,
+    attribute_ids: Vec<AttributeId>,
+    permanent: Vec<AttributeId>,
+) {
+    let attributes = storage.owners.entry(tamagotchi_id).or_default();
+    for attribute_id in attribute_ids {
+        let quantity = attributes.entry(attribute_id).or_default();
+        if *quantity > 0 && permanent.contains(&attribute_id) {
+            continue;
+        }
+        *quantity += 1;
+        storage
+            .attribute_owners
+            .entry(attribute_id)
+            .or_default()
+            .insert(tamagotchi_id);
+    }
+    msg::reply(StorageEvent::Granted, 0).expect("Error in sending a reply `StorageEvent::Granted`");
+}
+
+fn use_attribute(
+    storage: &mut StorageState,
+    tamagotchi_id: TamagotchiId,
+    attribute_id: AttributeId,
+) {
+    let Some(quantity) = storage
+        .owners
+        .get_mut(&tamagotchi_id)
+        .and_then(|attributes| attributes.get_mut(&attribute_id))
+    else {
+        msg::reply(StorageEvent::NotOwned, 0)
+            .expect("Error in sending a reply `StorageEvent::NotOwned`");
+        return;
+    };
+
+    *quantity -= 1;
+    let remaining = *quantity;
+    if remaining == 0 {
+        if let Some(attributes) = storage.owners.get_mut(&tamagotchi_id) {
+            attributes.remove(&attribute_id);
+        }
+        if let Some(owners) = storage.attribute_owners.get_mut(&attribute_id) {
+            owners.remove(&tamagotchi_id);
+        }
+    }
+    msg::reply(StorageEvent::Used { remaining }, 0)
+        .expect("Error in sending a reply `StorageEvent::Used`");
+}
+
+#[no_mangle]
+extern fn handle() {
+    let action: StorageAction = msg::load().expect("Unable to decode `StorageAction`");
+    let storage = unsafe { STORAGE.as_mut().expect("The contract is not initialized") };
+    assert_eq!(
+        msg::source(),
+        storage.store,
+        "Only the store can use its storage"
+    );
+
+    match action {
+        StorageAction::Grant {
+            tamagotchi_id,
+            attribute_ids,
+            permanent,
+        } => grant(storage, tamagotchi_id, attribute_ids, permanent),
+        StorageAction::Use {
+            tamagotchi_id,
+            attribute_id,
+        } => use_attribute(storage, tamagotchi_id, attribute_id),
+        StorageAction::GetAttributes { tamagotchi_id } => {
+            let attributes = storage
+                .owners
+                .get(&tamagotchi_id)
+                .cloned()
+                .unwrap_or_default();
+            msg::reply(StorageEvent::Attributes { attributes }, 0)
+                .expect("Error in sending a reply `StorageEvent::Attributes`");
+        }
+        StorageAction::OwnersOf {
+            attribute_id,
+            offset,
+            limit,
+        } => {
+            let owners = storage.attribute_owners.get(&attribute_id);
+            let total = owners.map_or(0, |owners| owners.len() as u32);
+            let owners = owners
+                .map(|owners| {
+                    owners
+                        .iter()
+                        .skip(offset as usize)
+                        .take(limit as usize)
+                        .copied()
+                        .collect()
+                })
+                .unwrap_or_default();
+            msg::reply(StorageEvent::Owners { owners, total }, 0)
+                .expect("Error in sending a reply `StorageEvent::Owners`");
+        }
+    }
+}
+
+#[no_mangle]
+extern fn init() {
+    let storage = StorageState {
+        store: msg::source(),
+        ..Default::default()
+    };
+    unsafe { STORAGE = Some(storage) };
+}
+
+#[no_mangle]
+extern fn state() {
+    let storage = unsafe { STORAGE.as_ref().expect("The contract is not initialized") };
+    let query: StorageQuery = msg::load().expect("Unable to decode `StorageQuery`");
+
+    let reply = match query {
+        StorageQuery::Full => StorageReply::Full(storage.clone()),
+        StorageQuery::AttributesOf { tamagotchi_id } => StorageReply::AttributesOf {
+            attributes: storage
+                .owners
+                .get(&tamagotchi_id)
+                .cloned()
+                .unwrap_or_default(),
+        },
+    };
+    msg::reply(reply, 0).expect("Failed to share state");
+}
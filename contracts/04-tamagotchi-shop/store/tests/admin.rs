@@ -0,0 +1,254 @@
+mod common;
+
+use common::*;
+use gtest::{Log, System};
+use sharded_fungible_token_io::{FTokenAction, FTokenEvent, LogicAction};
+use store_io::{Category, Currency, Role, StoreAction, StoreError, StoreEvent, TxState};
+
+#[test]
+fn withdraw() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+    assert!(replied(
+        &buy(&sys, &store, BUYER, HAT),
+        BUYER,
+        sold(TxState::Completed, 0)
+    ));
+
+    let withdraw = |currency| StoreAction::Withdraw {
+        currency,
+        to: 5.into(),
+        amount: PRICE,
+    };
+    let res = store.send(BUYER, withdraw(Currency::Native));
+    assert!(replied(&res, BUYER, Err(StoreError::Unauthorized)));
+
+    let res = store.send(ADMIN, withdraw(Currency::Native));
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::Withdrawn {
+            currency: Currency::Native,
+            to: 5.into(),
+            amount: PRICE,
+            success: true,
+        })
+    ));
+    sys.claim_value_from_mailbox(5);
+    assert_eq!(sys.balance_of(5), PRICE);
+    assert_eq!(sys.balance_of(store.id()), 0);
+
+    let res = store.send(ADMIN, withdraw(Currency::FungibleToken));
+    // the purchase took the first transaction ID
+    let transfer_log = Log::builder().dest(FT).payload(FTokenAction::Message {
+        transaction_id: 1,
+        payload: LogicAction::Transfer {
+            sender: store.id().into_bytes().into(),
+            recipient: 5.into(),
+            amount: PRICE,
+        },
+    });
+    assert!(res.contains(&transfer_log));
+
+    let res = sys.get_mailbox(FT).reply(transfer_log, FTokenEvent::Err, 0);
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::Withdrawn {
+            currency: Currency::FungibleToken,
+            to: 5.into(),
+            amount: PRICE,
+            success: false,
+        })
+    ));
+}
+
+#[test]
+fn roles_and_admin_handover() {
+    let sys = System::new();
+    let store = init_store(&sys);
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::TransferAdmin {
+            new_admin: 4.into(),
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::AdminOffered {
+            new_admin: 4.into()
+        })
+    ));
+
+    // the rights are only handed over once the new admin accepts them
+    let res = store.send(5, StoreAction::AcceptAdmin);
+    assert!(replied(&res, 5, Err(StoreError::Unauthorized)));
+    let res = store.send(4, StoreAction::Pause);
+    assert!(replied(&res, 4, Err(StoreError::Unauthorized)));
+
+    let res = store.send(4, StoreAction::AcceptAdmin);
+    assert!(replied(
+        &res,
+        4,
+        Ok(StoreEvent::AdminTransferred {
+            new_admin: 4.into()
+        })
+    ));
+    let res = store.send(ADMIN, StoreAction::Pause);
+    assert!(replied(&res, ADMIN, Err(StoreError::Unauthorized)));
+
+    let res = store.send(
+        4,
+        StoreAction::GrantRole {
+            account: 5.into(),
+            role: Role::CatalogManager,
+        },
+    );
+    assert!(replied(
+        &res,
+        4,
+        Ok(StoreEvent::RoleGranted {
+            account: 5.into(),
+            role: Role::CatalogManager,
+        })
+    ));
+
+    let create_hat = || {
+        store.send(
+            5,
+            StoreAction::CreateAttribute {
+                attribute_id: HAT,
+                attr_metadata: metadata("Hat", Category::Cosmetic),
+                price: PRICE,
+            },
+        )
+    };
+    let res = create_hat();
+    assert!(replied(
+        &res,
+        5,
+        Ok(StoreEvent::AttributeCreated { attribute_id: HAT })
+    ));
+    // a role grants only its own actions
+    let res = store.send(5, StoreAction::Pause);
+    assert!(replied(&res, 5, Err(StoreError::Unauthorized)));
+
+    let res = store.send(
+        4,
+        StoreAction::RevokeRole {
+            account: 5.into(),
+            role: Role::CatalogManager,
+        },
+    );
+    assert!(replied(
+        &res,
+        4,
+        Ok(StoreEvent::RoleRevoked {
+            account: 5.into(),
+            role: Role::CatalogManager,
+        })
+    ));
+    let res = create_hat();
+    assert!(replied(&res, 5, Err(StoreError::Unauthorized)));
+}
+
+#[test]
+fn pause() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+    create_attribute(&store, SWORD, Category::Cosmetic, Currency::FungibleToken);
+
+    let res = buy_with_tokens(&store, BUYER, SWORD);
+    let transfer_log = Log::builder()
+        .dest(FT)
+        .payload(transfer(0, &store, BUYER, PRICE));
+    assert!(res.contains(&transfer_log));
+
+    let res = store.send(BUYER, StoreAction::Pause);
+    assert!(replied(&res, BUYER, Err(StoreError::Unauthorized)));
+
+    let res = store.send(ADMIN, StoreAction::Pause);
+    assert!(replied(&res, ADMIN, Ok(StoreEvent::Paused)));
+
+    let res = buy(&sys, &store, 4, HAT);
+    assert!(replied(&res, 4, Err(StoreError::Paused)));
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::CreateAttribute {
+            attribute_id: FOOD,
+            attr_metadata: metadata("Food", Category::Food(10)),
+            price: PRICE,
+        },
+    );
+    assert!(replied(&res, ADMIN, Err(StoreError::Paused)));
+
+    // a purchase started before the pause can still be finished
+    let res = buy_with_tokens(&store, BUYER, SWORD);
+    assert!(res.contains(&transfer_log));
+    for _ in 0..2 {
+        let res = sys
+            .get_mailbox(FT)
+            .reply(transfer_log.clone(), FTokenEvent::Ok, 0);
+        assert!(replied(&res, BUYER, sold(TxState::Completed, 0)));
+    }
+
+    let res = store.send(ADMIN, StoreAction::Unpause);
+    assert!(replied(&res, ADMIN, Ok(StoreEvent::Unpaused)));
+
+    let res = buy(&sys, &store, 4, HAT);
+    assert!(replied(&res, 4, sold(TxState::Completed, 0)));
+}
+
+#[test]
+fn registrar() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::SetRegistrar {
+            registrar: Some(6.into()),
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::RegistrarSet {
+            registrar: Some(6.into()),
+        })
+    ));
+
+    // only registered tamagotchis can buy or be given attributes
+    let res = buy(&sys, &store, BUYER, HAT);
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::UnknownTamagotchi {
+            tamagotchi_id: BUYER.into(),
+        })
+    ));
+
+    let register = |tamagotchi_id: u64| StoreAction::RegisterTamagotchi {
+        tamagotchi_id: tamagotchi_id.into(),
+    };
+    let res = store.send(BUYER, register(BUYER));
+    assert!(replied(&res, BUYER, Err(StoreError::Unauthorized)));
+
+    let res = store.send(6, register(BUYER));
+    assert!(replied(
+        &res,
+        6,
+        Ok(StoreEvent::TamagotchiRegistered {
+            tamagotchi_id: BUYER.into(),
+        })
+    ));
+
+    let res = buy(&sys, &store, BUYER, HAT);
+    assert!(replied(&res, BUYER, sold(TxState::Completed, 0)));
+}
@@ -0,0 +1,268 @@
+mod common;
+
+use common::*;
+use gtest::System;
+use store_io::{
+    AttrMetadata, Category, Currency, Media, MetadataError, Slot, StoreAction, StoreError,
+    StoreEvent, MAX_DESCRIPTION_LEN, MAX_TITLE_LEN,
+};
+
+#[test]
+fn catalog_management() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+
+    let res = store.send(
+        BUYER,
+        StoreAction::SetPrice {
+            attribute_id: HAT,
+            price: PRICE / 2,
+        },
+    );
+    assert!(replied(&res, BUYER, Err(StoreError::Unauthorized)));
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::SetPrice {
+            attribute_id: HAT,
+            price: PRICE / 2,
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::PriceSet {
+            attribute_id: HAT,
+            price: PRICE / 2,
+        })
+    ));
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::UpdateAttribute {
+            attribute_id: HAT,
+            attr_metadata: metadata("Top hat", Category::Cosmetic),
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::AttributeUpdated { attribute_id: HAT })
+    ));
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::UpdateAttribute {
+            attribute_id: SWORD,
+            attr_metadata: metadata("Sword", Category::Cosmetic),
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Err(StoreError::AttributeNotFound {
+            attribute_id: SWORD
+        })
+    ));
+
+    let res = store.send(ADMIN, StoreAction::GetAttribute { attribute_id: HAT });
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::Attribute {
+            attr_metadata: metadata("Top hat", Category::Cosmetic),
+            price: PRICE / 2,
+        })
+    ));
+
+    let res = store.send(ADMIN, StoreAction::RetireAttribute { attribute_id: HAT });
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::AttributeRetired { attribute_id: HAT })
+    ));
+
+    let res = buy(&sys, &store, BUYER, HAT);
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::AttributeRetired { attribute_id: HAT })
+    ));
+
+    // owners still resolve the metadata of a retired attribute
+    let res = store.send(BUYER, StoreAction::GetAttribute { attribute_id: HAT });
+    assert!(replied(
+        &res,
+        BUYER,
+        Ok(StoreEvent::Attribute {
+            attr_metadata: metadata("Top hat", Category::Cosmetic),
+            price: PRICE / 2,
+        })
+    ));
+}
+
+#[test]
+fn batch_creation() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+
+    let create = |attributes| store.send(ADMIN, StoreAction::CreateAttributes { attributes });
+
+    // nothing is created if a single attribute of the batch can`t be
+    let res = create(vec![
+        (SWORD, metadata("Sword", Category::Cosmetic), PRICE),
+        (HAT, metadata("Hat", Category::Cosmetic), PRICE),
+    ]);
+    assert!(replied(
+        &res,
+        ADMIN,
+        Err(StoreError::AttributeAlreadyExists { attribute_id: HAT })
+    ));
+
+    let res = create(vec![
+        (SWORD, metadata("Sword", Category::Cosmetic), PRICE),
+        (SWORD, metadata("Sword", Category::Cosmetic), PRICE),
+    ]);
+    assert!(replied(&res, ADMIN, Err(StoreError::DuplicateAttributes)));
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::GetAttribute {
+            attribute_id: SWORD,
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Err(StoreError::AttributeNotFound {
+            attribute_id: SWORD
+        })
+    ));
+
+    let res = create(vec![
+        (SWORD, metadata("Sword", Category::Cosmetic), PRICE),
+        (FOOD, metadata("Food", Category::Food(10)), PRICE),
+    ]);
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::AttributesCreated {
+            attribute_ids: vec![SWORD, FOOD],
+        })
+    ));
+}
+
+#[test]
+fn metadata_validation() {
+    let sys = System::new();
+    let store = init_store(&sys);
+
+    let with = |update: fn(&mut AttrMetadata)| {
+        let mut attr_metadata = metadata("Hat", Category::Cosmetic);
+        update(&mut attr_metadata);
+        attr_metadata
+    };
+    let invalid = [
+        (
+            with(|m| m.title = String::from(" ")),
+            MetadataError::EmptyTitle,
+        ),
+        (
+            with(|m| m.title = "a".repeat(MAX_TITLE_LEN + 1)),
+            MetadataError::TitleTooLong,
+        ),
+        (
+            with(|m| m.description = "a".repeat(MAX_DESCRIPTION_LEN + 1)),
+            MetadataError::DescriptionTooLong,
+        ),
+        (
+            with(|m| m.media = Media::Ipfs(String::from("ipfs://QmHash"))),
+            MetadataError::InvalidMedia,
+        ),
+        (
+            with(|m| m.media = Media::Https(String::from("http://example.com/hat.png"))),
+            MetadataError::InvalidMedia,
+        ),
+        (
+            with(|m| {
+                m.category = Category::Food(10);
+                m.slot = Some(Slot::Head);
+            }),
+            MetadataError::WearableConsumable,
+        ),
+    ];
+    for (attr_metadata, error) in invalid {
+        let res = store.send(
+            ADMIN,
+            StoreAction::CreateAttribute {
+                attribute_id: HAT,
+                attr_metadata,
+                price: PRICE,
+            },
+        );
+        assert!(replied(
+            &res,
+            ADMIN,
+            Err(StoreError::InvalidMetadata {
+                attribute_id: HAT,
+                error,
+            })
+        ));
+    }
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::CreateAttributes {
+            attributes: vec![
+                (
+                    HAT,
+                    with(|m| m.media = Media::Ipfs(String::from("QmHash"))),
+                    PRICE,
+                ),
+                (FOOD, metadata(" ", Category::Food(10)), PRICE),
+            ],
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Err(StoreError::InvalidMetadata {
+            attribute_id: FOOD,
+            error: MetadataError::EmptyTitle,
+        })
+    ));
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::CreateAttribute {
+            attribute_id: HAT,
+            attr_metadata: with(|m| {
+                m.media = Media::Https(String::from("https://example.com/hat.png"))
+            }),
+            price: PRICE,
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::AttributeCreated { attribute_id: HAT })
+    ));
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::UpdateAttribute {
+            attribute_id: HAT,
+            attr_metadata: with(|m| m.title = String::new()),
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Err(StoreError::InvalidMetadata {
+            attribute_id: HAT,
+            error: MetadataError::EmptyTitle,
+        })
+    ));
+}
@@ -0,0 +1,157 @@
+#![allow(dead_code)]
+
+use gear_core::ids::CodeId;
+use gtest::{Log, Program, RunResult, System};
+use sharded_fungible_token_io::{FTokenAction, LogicAction};
+use std::{fs, path::PathBuf};
+use store_io::{
+    AttrMetadata, AttributeId, Category, Currency, InitStore, Media, Price, Sprite, StateQuery,
+    StateReply, StoreAction, StoreError, StoreEvent, TxState,
+};
+
+pub const ADMIN: u64 = 2;
+pub const BUYER: u64 = 3;
+// a user stands in for the token contract, so the tests decide how each transfer ends
+pub const FT: u64 = 100;
+
+pub const HAT: AttributeId = 1;
+pub const SWORD: AttributeId = 2;
+pub const FOOD: AttributeId = 3;
+// values below the existential deposit of 10 VARA can`t be sent, so prices are whole VARA
+pub const VARA: Price = 1_000_000_000_000;
+pub const PRICE: Price = 100 * VARA;
+
+/// Returns the storage binary, which is built along with the store as its build dependency.
+pub fn storage_wasm() -> PathBuf {
+    let storage_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("storage");
+    let binpath = fs::read_to_string(storage_dir.join(".binpath"))
+        .expect("Unable to read the path of the storage binary");
+    storage_dir.join(binpath.trim()).with_extension("opt.wasm")
+}
+
+pub fn init_store(sys: &System) -> Program<'_> {
+    let storage_code_id = sys.submit_code(storage_wasm());
+    init_store_with(sys, storage_code_id)
+}
+
+pub fn init_store_with(sys: &System, storage_code_id: CodeId) -> Program<'_> {
+    sys.init_logger();
+    let store = Program::current(sys);
+
+    let res = store.send(
+        ADMIN,
+        InitStore {
+            ft_contract_id: FT.into(),
+            storage_code_id: storage_code_id.into_bytes().into(),
+        },
+    );
+    assert!(!res.main_failed());
+    store
+}
+
+pub fn metadata(title: &str, category: Category) -> AttrMetadata {
+    AttrMetadata {
+        title: String::from(title),
+        description: String::from("Description"),
+        media: Media::Sprite(Sprite::Hat),
+        effects: vec![],
+        category,
+        slot: None,
+    }
+}
+
+pub fn create_attribute(
+    store: &Program<'_>,
+    attribute_id: AttributeId,
+    category: Category,
+    currency: Currency,
+) {
+    let res = store.send(
+        ADMIN,
+        StoreAction::CreateAttribute {
+            attribute_id,
+            attr_metadata: metadata("Attribute", category),
+            price: PRICE,
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::AttributeCreated { attribute_id })
+    ));
+
+    if currency != Currency::FungibleToken {
+        let res = store.send(
+            ADMIN,
+            StoreAction::SetCurrency {
+                attribute_id,
+                currency,
+            },
+        );
+        assert!(replied(
+            &res,
+            ADMIN,
+            Ok(StoreEvent::CurrencySet {
+                attribute_id,
+                currency,
+            })
+        ));
+    }
+}
+
+/// Buys the attribute for its base price in the native value.
+pub fn buy(sys: &System, store: &Program<'_>, buyer: u64, attribute_id: AttributeId) -> RunResult {
+    sys.mint_to(buyer, PRICE);
+    store.send_with_value(
+        buyer,
+        StoreAction::BuyAttribute {
+            attribute_id,
+            max_price: None,
+            coupon: None,
+        },
+        PRICE,
+    )
+}
+
+/// Buys the attribute for its base price in the tokens of its currency.
+pub fn buy_with_tokens(store: &Program<'_>, buyer: u64, attribute_id: AttributeId) -> RunResult {
+    store.send(
+        buyer,
+        StoreAction::BuyAttribute {
+            attribute_id,
+            max_price: None,
+            coupon: None,
+        },
+    )
+}
+
+/// The transfer the store asks the token contract for to charge the buyer.
+pub fn transfer(
+    transaction_id: u64,
+    store: &Program<'_>,
+    sender: u64,
+    amount: Price,
+) -> FTokenAction {
+    FTokenAction::Message {
+        transaction_id,
+        payload: LogicAction::Transfer {
+            sender: sender.into(),
+            recipient: store.id().into_bytes().into(),
+            amount,
+        },
+    }
+}
+
+pub fn replied(res: &RunResult, to: u64, reply: Result<StoreEvent, StoreError>) -> bool {
+    res.contains(&Log::builder().dest(to).payload(reply))
+}
+
+pub fn sold(state: TxState, change: u128) -> Result<StoreEvent, StoreError> {
+    Ok(StoreEvent::AttributeSold { state, change })
+}
+
+pub fn read_state(store: &Program<'_>, query: StateQuery) -> StateReply {
+    store
+        .read_state(query)
+        .expect("Unable to read the store state")
+}
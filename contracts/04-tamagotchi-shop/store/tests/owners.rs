@@ -0,0 +1,124 @@
+mod common;
+
+use common::*;
+use gstd::ActorId;
+use gtest::{Program, System};
+use store_io::{
+    AttributeId, Category, Currency, Quantity, StateQuery, StateReply, StoreAction, StoreEvent,
+    TxState,
+};
+use store_storage_io::{StorageQuery, StorageReply};
+
+#[test]
+fn owners_across_shards() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+
+    // the first byte of these IDs puts them into the shards 0, 1 and 2
+    let buyers: [u64; 3] = [0x03, 0x13, 0x23];
+    for buyer in buyers {
+        assert!(replied(
+            &buy(&sys, &store, buyer, HAT),
+            buyer,
+            sold(TxState::Completed, 0)
+        ));
+    }
+
+    for (offset, limit, owners) in [
+        (0, 2, &buyers[..2]),
+        (1, 5, &buyers[1..]),
+        (2, 2, &buyers[2..]),
+        (3, 2, &buyers[3..]),
+    ] {
+        let res = store.send(
+            ADMIN,
+            StoreAction::OwnersOf {
+                attribute_id: HAT,
+                offset,
+                limit,
+            },
+        );
+        assert!(replied(
+            &res,
+            ADMIN,
+            Ok(StoreEvent::Owners {
+                attribute_id: HAT,
+                owners: owners.iter().map(|&owner| owner.into()).collect(),
+            })
+        ));
+    }
+}
+
+fn storage_of(store: &Program<'_>, tamagotchi_id: u64) -> Option<ActorId> {
+    let StateReply::AttributesStorageOf { storage } = read_state(
+        store,
+        StateQuery::AttributesStorageOf {
+            tamagotchi_id: tamagotchi_id.into(),
+        },
+    ) else {
+        panic!("The store replied to `AttributesStorageOf` with another reply");
+    };
+    storage
+}
+
+fn attributes_in(
+    sys: &System,
+    storage: ActorId,
+    tamagotchi_id: u64,
+) -> Vec<(AttributeId, Quantity)> {
+    let reply: StorageReply = sys
+        .get_program(<[u8; 32]>::from(storage))
+        .read_state(StorageQuery::AttributesOf {
+            tamagotchi_id: tamagotchi_id.into(),
+        })
+        .expect("Unable to read the storage state");
+    let StorageReply::AttributesOf { attributes } = reply else {
+        panic!("The storage replied to `AttributesOf` with another reply");
+    };
+    attributes.into_iter().collect()
+}
+
+#[test]
+fn storage_shards() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+    create_attribute(&store, SWORD, Category::Cosmetic, Currency::Native);
+
+    // 0x03 and 0x04 share the shard 0, 0x13 is in the shard 1
+    for (buyer, attribute_id) in [(0x03, HAT), (0x04, SWORD), (0x13, HAT)] {
+        assert!(replied(
+            &buy(&sys, &store, buyer, attribute_id),
+            buyer,
+            sold(TxState::Completed, 0)
+        ));
+    }
+
+    let first = storage_of(&store, 0x03).expect("The shard 0 has no storage");
+    let second = storage_of(&store, 0x13).expect("The shard 1 has no storage");
+    assert_ne!(first, second);
+    assert_eq!(storage_of(&store, 0x04), Some(first));
+    // a storage is created only when its shard gets its first attribute
+    assert_eq!(storage_of(&store, 0x23), None);
+
+    assert_eq!(attributes_in(&sys, first, 0x03), [(HAT, 1)]);
+    assert_eq!(attributes_in(&sys, first, 0x04), [(SWORD, 1)]);
+    assert_eq!(attributes_in(&sys, first, 0x13), []);
+    assert_eq!(attributes_in(&sys, second, 0x13), [(HAT, 1)]);
+
+    // queries sent to the store are forwarded to the shard of the tamagotchi
+    let res = store.send(
+        0x13,
+        StoreAction::GetAttributes {
+            tamagotchi_id: 0x13.into(),
+        },
+    );
+    assert!(replied(
+        &res,
+        0x13,
+        Ok(StoreEvent::Attributes {
+            attributes: [HAT].into(),
+        })
+    ));
+}
@@ -0,0 +1,221 @@
+mod common;
+
+use common::*;
+use gtest::{Log, System};
+use sharded_fungible_token_io::FTokenEvent;
+use store_io::{
+    AttributeId, Category, Coupon, Currency, Discount, PriceCurve, StoreAction, StoreError,
+    StoreEvent, TxState,
+};
+
+#[test]
+fn price_curve() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+    create_attribute(&store, SWORD, Category::Cosmetic, Currency::Native);
+
+    for (attribute_id, decay_per_block) in [(HAT, 0), (SWORD, VARA)] {
+        let curve = Some(PriceCurve {
+            step: 10 * VARA,
+            decay_per_block,
+        });
+        let res = store.send(
+            ADMIN,
+            StoreAction::SetPriceCurve {
+                attribute_id,
+                curve,
+            },
+        );
+        assert!(replied(
+            &res,
+            ADMIN,
+            Ok(StoreEvent::PriceCurveSet {
+                attribute_id,
+                curve,
+            })
+        ));
+        assert!(replied(
+            &buy(&sys, &store, BUYER, attribute_id),
+            BUYER,
+            sold(TxState::Completed, 0)
+        ));
+    }
+
+    // each sale raises the price by a step
+    let res = store.send(ADMIN, StoreAction::GetPrice { attribute_id: HAT });
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::Price {
+            attribute_id: HAT,
+            price: PRICE + 10 * VARA,
+        })
+    ));
+
+    let res = store.send(
+        4,
+        StoreAction::BuyAttribute {
+            attribute_id: HAT,
+            max_price: Some(PRICE),
+            coupon: None,
+        },
+    );
+    assert!(replied(
+        &res,
+        4,
+        Err(StoreError::PriceExceedsMax {
+            price: PRICE + 10 * VARA,
+            max_price: PRICE,
+        })
+    ));
+
+    // the premium decays back to the base price
+    sys.spend_blocks(20);
+    let res = store.send(
+        ADMIN,
+        StoreAction::GetPrice {
+            attribute_id: SWORD,
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::Price {
+            attribute_id: SWORD,
+            price: PRICE,
+        })
+    ));
+}
+
+#[test]
+fn coupons() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+    create_attribute(&store, SWORD, Category::Cosmetic, Currency::Native);
+
+    let create_coupon = |code: &str, coupon| {
+        let code = String::from(code);
+        let res = store.send(
+            ADMIN,
+            StoreAction::CreateCoupon {
+                code: code.clone(),
+                coupon,
+            },
+        );
+        assert!(replied(&res, ADMIN, Ok(StoreEvent::CouponCreated { code })));
+    };
+    create_coupon(
+        "HALF",
+        Coupon {
+            discount: Discount::Percent(50),
+            uses_left: 1,
+            expires_at: 1_000,
+            attribute_id: Some(HAT),
+        },
+    );
+    create_coupon(
+        "SOON",
+        Coupon {
+            discount: Discount::Fixed(10 * VARA),
+            uses_left: 1,
+            expires_at: 10,
+            attribute_id: None,
+        },
+    );
+
+    let buy_with_coupon = |buyer: u64, attribute_id: AttributeId, code: &str| {
+        sys.mint_to(buyer, PRICE);
+        store.send_with_value(
+            buyer,
+            StoreAction::BuyAttribute {
+                attribute_id,
+                max_price: None,
+                coupon: Some(String::from(code)),
+            },
+            PRICE,
+        )
+    };
+
+    let res = buy_with_coupon(BUYER, SWORD, "HALF");
+    assert!(replied(&res, BUYER, Err(StoreError::CouponNotApplicable)));
+
+    let res = buy_with_coupon(BUYER, HAT, "HALF");
+    assert!(replied(&res, BUYER, sold(TxState::Completed, PRICE / 2)));
+
+    let res = buy_with_coupon(4, HAT, "HALF");
+    assert!(replied(&res, 4, Err(StoreError::CouponExhausted)));
+
+    sys.spend_blocks(10);
+    let res = buy_with_coupon(4, SWORD, "SOON");
+    assert!(replied(&res, 4, Err(StoreError::CouponExpired)));
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::RemoveCoupon {
+            code: String::from("SOON"),
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::CouponRemoved {
+            code: String::from("SOON"),
+        })
+    ));
+    let res = buy_with_coupon(4, SWORD, "SOON");
+    assert!(replied(&res, 4, Err(StoreError::CouponNotFound)));
+}
+
+#[test]
+fn coupon_of_failed_purchase() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::FungibleToken);
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::CreateCoupon {
+            code: String::from("TEN"),
+            coupon: Coupon {
+                discount: Discount::Fixed(10 * VARA),
+                uses_left: 1,
+                expires_at: 1_000,
+                attribute_id: None,
+            },
+        },
+    );
+    assert!(!res.main_failed());
+
+    let buy_with_coupon = || {
+        store.send(
+            BUYER,
+            StoreAction::BuyAttribute {
+                attribute_id: HAT,
+                max_price: None,
+                coupon: Some(String::from("TEN")),
+            },
+        )
+    };
+    let res = buy_with_coupon();
+    let transfer_log =
+        Log::builder()
+            .dest(FT)
+            .payload(transfer(0, &store, BUYER, PRICE - 10 * VARA));
+    assert!(res.contains(&transfer_log));
+
+    // a failed transfer gives the coupon use back
+    let res = sys.get_mailbox(FT).reply(transfer_log, FTokenEvent::Err, 0);
+    assert!(replied(&res, BUYER, sold(TxState::Failed, 0)));
+
+    let res = buy_with_coupon();
+    let transfer_log =
+        Log::builder()
+            .dest(FT)
+            .payload(transfer(1, &store, BUYER, PRICE - 10 * VARA));
+    assert!(res.contains(&transfer_log));
+
+    let res = sys.get_mailbox(FT).reply(transfer_log, FTokenEvent::Ok, 0);
+    assert!(replied(&res, BUYER, sold(TxState::Completed, 0)));
+}
@@ -0,0 +1,381 @@
+mod common;
+
+use common::*;
+use gtest::{Log, System};
+use sharded_fungible_token_io::FTokenEvent;
+use store_io::{
+    Category, Currency, StateQuery, StateReply, StoreAction, StoreError, StoreEvent, TxState,
+};
+use store_storage_io::{StorageQuery, StorageReply};
+
+#[test]
+fn bundle_purchase() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+    create_attribute(&store, SWORD, Category::Cosmetic, Currency::Native);
+    create_attribute(&store, FOOD, Category::Food(10), Currency::FungibleToken);
+
+    let res = store.send(ADMIN, StoreAction::SetBundleDiscount { discount: 10 });
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::BundleDiscountSet { discount: 10 })
+    ));
+
+    let res = store.send(
+        BUYER,
+        StoreAction::BuyAttributes {
+            attribute_ids: vec![],
+        },
+    );
+    assert!(replied(&res, BUYER, Err(StoreError::EmptyBundle)));
+
+    let res = store.send(
+        BUYER,
+        StoreAction::BuyAttributes {
+            attribute_ids: vec![HAT, HAT],
+        },
+    );
+    assert!(replied(&res, BUYER, Err(StoreError::DuplicateAttributes)));
+
+    let res = store.send(
+        BUYER,
+        StoreAction::BuyAttributes {
+            attribute_ids: vec![HAT, FOOD],
+        },
+    );
+    assert!(replied(&res, BUYER, Err(StoreError::MixedCurrencies)));
+
+    assert!(replied(
+        &buy(&sys, &store, BUYER, HAT),
+        BUYER,
+        sold(TxState::Completed, 0)
+    ));
+
+    // nothing is sold if a single attribute of the bundle can`t be
+    sys.mint_to(BUYER, 2 * PRICE);
+    let res = store.send_with_value(
+        BUYER,
+        StoreAction::BuyAttributes {
+            attribute_ids: vec![SWORD, HAT],
+        },
+        2 * PRICE,
+    );
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::AlreadyOwned { attribute_id: HAT })
+    ));
+
+    sys.mint_to(4, 2 * PRICE);
+    let res = store.send_with_value(
+        4,
+        StoreAction::BuyAttributes {
+            attribute_ids: vec![SWORD, HAT],
+        },
+        2 * PRICE,
+    );
+    assert!(replied(
+        &res,
+        4,
+        Ok(StoreEvent::AttributesSold {
+            state: TxState::Completed,
+            change: 2 * PRICE / 10,
+        })
+    ));
+
+    let res = store.send(
+        4,
+        StoreAction::GetAttributes {
+            tamagotchi_id: 4.into(),
+        },
+    );
+    assert!(replied(
+        &res,
+        4,
+        Ok(StoreEvent::Attributes {
+            attributes: [HAT, SWORD].into(),
+        })
+    ));
+}
+
+#[test]
+fn native_purchase() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+
+    sys.mint_to(BUYER, 2 * PRICE);
+    let res = store.send_with_value(
+        BUYER,
+        StoreAction::BuyAttribute {
+            attribute_id: HAT,
+            max_price: None,
+            coupon: None,
+        },
+        PRICE / 2,
+    );
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::InsufficientValue {
+            price: PRICE,
+            value: PRICE / 2,
+        })
+    ));
+    // a rejected purchase returns all the attached value
+    sys.claim_value_from_mailbox(BUYER);
+    assert_eq!(sys.balance_of(BUYER), 2 * PRICE);
+
+    let res = store.send_with_value(
+        BUYER,
+        StoreAction::BuyAttribute {
+            attribute_id: HAT,
+            max_price: None,
+            coupon: None,
+        },
+        PRICE + PRICE / 5,
+    );
+    assert!(replied(&res, BUYER, sold(TxState::Completed, PRICE / 5)));
+    sys.claim_value_from_mailbox(BUYER);
+    assert_eq!(sys.balance_of(BUYER), PRICE);
+    assert_eq!(sys.balance_of(store.id()), PRICE);
+
+    // a permanent attribute can`t be bought twice
+    let res = buy(&sys, &store, BUYER, HAT);
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::AlreadyOwned { attribute_id: HAT })
+    ));
+}
+
+#[test]
+fn gift() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+
+    let buy_for_buyer = || {
+        sys.mint_to(4, PRICE);
+        store.send_with_value(
+            4,
+            StoreAction::BuyAttributeFor {
+                attribute_id: HAT,
+                recipient: BUYER.into(),
+            },
+            PRICE,
+        )
+    };
+    let res = buy_for_buyer();
+    assert!(replied(
+        &res,
+        4,
+        Ok(StoreEvent::AttributeSoldFor {
+            attribute_id: HAT,
+            payer: 4.into(),
+            recipient: BUYER.into(),
+            state: TxState::Completed,
+            change: 0,
+        })
+    ));
+
+    let res = store.send(
+        4,
+        StoreAction::GetAttributes {
+            tamagotchi_id: BUYER.into(),
+        },
+    );
+    assert!(replied(
+        &res,
+        4,
+        Ok(StoreEvent::Attributes {
+            attributes: [HAT].into(),
+        })
+    ));
+
+    let res = buy_for_buyer();
+    assert!(replied(
+        &res,
+        4,
+        Err(StoreError::AlreadyOwned { attribute_id: HAT })
+    ));
+    let res = buy(&sys, &store, BUYER, HAT);
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::AlreadyOwned { attribute_id: HAT })
+    ));
+}
+
+#[test]
+fn no_double_charge() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::FungibleToken);
+
+    let res = buy_with_tokens(&store, BUYER, HAT);
+    let transfer_log = Log::builder()
+        .dest(FT)
+        .payload(transfer(0, &store, BUYER, PRICE));
+    assert!(res.contains(&transfer_log));
+
+    // the hat is reserved for the buyer until the first purchase is finished
+    let gift = || {
+        store.send(
+            4,
+            StoreAction::BuyAttributeFor {
+                attribute_id: HAT,
+                recipient: BUYER.into(),
+            },
+        )
+    };
+    let res = gift();
+    assert!(replied(
+        &res,
+        4,
+        Err(StoreError::PurchaseInProgress { attribute_id: HAT })
+    ));
+
+    let res = sys.get_mailbox(FT).reply(transfer_log, FTokenEvent::Ok, 0);
+    assert!(replied(&res, BUYER, sold(TxState::Completed, 0)));
+
+    let res = gift();
+    assert!(replied(
+        &res,
+        4,
+        Err(StoreError::AlreadyOwned { attribute_id: HAT })
+    ));
+}
+
+#[test]
+fn quantities() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, FOOD, Category::Food(10), Currency::Native);
+
+    // consumables stack
+    for _ in 0..2 {
+        assert!(replied(
+            &buy(&sys, &store, BUYER, FOOD),
+            BUYER,
+            sold(TxState::Completed, 0)
+        ));
+    }
+
+    let StateReply::AttributesStorageOf {
+        storage: Some(storage),
+    } = read_state(
+        &store,
+        StateQuery::AttributesStorageOf {
+            tamagotchi_id: BUYER.into(),
+        },
+    )
+    else {
+        panic!("The attributes of the buyer aren`t stored");
+    };
+    let reply: StorageReply = sys
+        .get_program(<[u8; 32]>::from(storage))
+        .read_state(StorageQuery::AttributesOf {
+            tamagotchi_id: BUYER.into(),
+        })
+        .expect("Unable to read the storage state");
+    let StorageReply::AttributesOf { attributes } = reply else {
+        panic!("The storage replied to `AttributesOf` with another reply");
+    };
+    assert_eq!(attributes.into_iter().collect::<Vec<_>>(), [(FOOD, 2)]);
+}
+
+#[test]
+fn consumables() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+    create_attribute(&store, FOOD, Category::Food(10), Currency::Native);
+
+    for attribute_id in [FOOD, FOOD, HAT] {
+        assert!(replied(
+            &buy(&sys, &store, BUYER, attribute_id),
+            BUYER,
+            sold(TxState::Completed, 0)
+        ));
+    }
+
+    let res = store.send(BUYER, StoreAction::UseAttribute { attribute_id: HAT });
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::NotConsumable { attribute_id: HAT })
+    ));
+
+    for remaining in [1, 0] {
+        let res = store.send(BUYER, StoreAction::UseAttribute { attribute_id: FOOD });
+        assert!(replied(
+            &res,
+            BUYER,
+            Ok(StoreEvent::AttributeUsed {
+                attribute_id: FOOD,
+                category: Category::Food(10),
+                remaining,
+            })
+        ));
+    }
+
+    let res = store.send(BUYER, StoreAction::UseAttribute { attribute_id: FOOD });
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::NotOwned { attribute_id: FOOD })
+    ));
+}
+
+#[test]
+fn token_currency() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    // the hat is paid with the tokens of another contract than the store`s default one
+    let currency = Currency::Token(101.into());
+    create_attribute(&store, HAT, Category::Cosmetic, currency);
+
+    let res = buy_with_tokens(&store, BUYER, HAT);
+    let transfer_log = Log::builder()
+        .dest(101)
+        .payload(transfer(0, &store, BUYER, PRICE));
+    assert!(res.contains(&transfer_log));
+    assert!(!res.contains(&Log::builder().dest(FT)));
+
+    let res = sys.get_mailbox(101).reply(transfer_log, FTokenEvent::Ok, 0);
+    assert!(replied(&res, BUYER, sold(TxState::Completed, 0)));
+
+    let StateReply::Full(state) = read_state(&store, StateQuery::Full) else {
+        panic!("The store replied to `Full` with another reply");
+    };
+    assert_eq!(state.revenue.get(&currency), Some(&PRICE));
+    assert_eq!(state.revenue.get(&Currency::FungibleToken), None);
+}
+
+#[test]
+fn errors_are_replied() {
+    let sys = System::new();
+    let store = init_store(&sys);
+
+    // a rejected purchase doesn`t fail the message and returns the attached value
+    let res = buy(&sys, &store, BUYER, HAT);
+    assert!(!res.main_failed());
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::AttributeNotFound { attribute_id: HAT })
+    ));
+    sys.claim_value_from_mailbox(BUYER);
+    assert_eq!(sys.balance_of(BUYER), PRICE);
+
+    let res = store.send(BUYER, StoreAction::UseAttribute { attribute_id: HAT });
+    assert!(!res.main_failed());
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::AttributeNotFound { attribute_id: HAT })
+    ));
+}
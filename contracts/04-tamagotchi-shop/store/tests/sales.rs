@@ -0,0 +1,154 @@
+mod common;
+
+use common::*;
+use gtest::{Log, Program, RunResult, System};
+use store_io::{
+    Category, Currency, Purchase, StateQuery, StateReply, StoreAction, StoreEvent, TxState,
+};
+
+fn purchases(store: &Program<'_>, offset: u32, limit: u32) -> Vec<Purchase> {
+    let StateReply::Purchases { purchases } =
+        read_state(store, StateQuery::Purchases { offset, limit })
+    else {
+        panic!("The store replied to `Purchases` with another reply");
+    };
+    purchases
+}
+
+#[test]
+fn purchase_history() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+    create_attribute(&store, SWORD, Category::Cosmetic, Currency::Native);
+    create_attribute(&store, FOOD, Category::Food(10), Currency::Native);
+
+    for (buyer, attribute_id) in [(BUYER, HAT), (4, HAT), (4, SWORD)] {
+        assert!(replied(
+            &buy(&sys, &store, buyer, attribute_id),
+            buyer,
+            sold(TxState::Completed, 0)
+        ));
+    }
+
+    let all = purchases(&store, 0, 10);
+    let sold_items = all
+        .iter()
+        .map(|purchase| (purchase.buyer, purchase.attribute_id, purchase.price))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sold_items,
+        [
+            (BUYER.into(), HAT, PRICE),
+            (4.into(), HAT, PRICE),
+            (4.into(), SWORD, PRICE)
+        ]
+    );
+    assert!(all
+        .iter()
+        .all(|purchase| purchase.currency == Currency::Native));
+
+    // the action pages the history the same way as the state
+    let page = purchases(&store, 1, 1);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[0].attribute_id, HAT);
+    let res = store.send(
+        ADMIN,
+        StoreAction::GetPurchases {
+            offset: 1,
+            limit: 1,
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::Purchases { purchases: page })
+    ));
+
+    for (attribute_id, units_sold) in [(HAT, 2), (SWORD, 1), (FOOD, 0)] {
+        let StateReply::Sales { sales } = read_state(&store, StateQuery::Sales { attribute_id })
+        else {
+            panic!("The store replied to `Sales` with another reply");
+        };
+        assert_eq!(sales.units_sold, units_sold);
+        assert_eq!(sales.revenue, PRICE * units_sold as u128);
+    }
+}
+
+#[test]
+fn listener() {
+    const LISTENER: u64 = 7;
+
+    let sys = System::new();
+    let store = init_store(&sys);
+    let notified = |res: &RunResult, event: StoreEvent| {
+        res.contains(&Log::builder().dest(LISTENER).payload(event))
+    };
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::SetListener {
+            listener: Some(LISTENER.into()),
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::ListenerSet {
+            listener: Some(LISTENER.into()),
+        })
+    ));
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::CreateAttribute {
+            attribute_id: HAT,
+            attr_metadata: metadata("Hat", Category::Cosmetic),
+            price: PRICE,
+        },
+    );
+    assert!(notified(
+        &res,
+        StoreEvent::AttributeCreated { attribute_id: HAT }
+    ));
+    let res = store.send(
+        ADMIN,
+        StoreAction::SetCurrency {
+            attribute_id: HAT,
+            currency: Currency::Native,
+        },
+    );
+    assert!(!res.main_failed());
+
+    sys.mint_to(BUYER, 2 * PRICE);
+    let res = store.send_with_value(
+        BUYER,
+        StoreAction::BuyAttribute {
+            attribute_id: HAT,
+            max_price: None,
+            coupon: None,
+        },
+        PRICE + PRICE / 2,
+    );
+    assert!(replied(&res, BUYER, sold(TxState::Completed, PRICE / 2)));
+    assert!(notified(
+        &res,
+        StoreEvent::Purchases {
+            purchases: purchases(&store, 0, 1),
+        }
+    ));
+    assert!(notified(
+        &res,
+        StoreEvent::Refunded {
+            to: BUYER.into(),
+            amount: PRICE / 2,
+        }
+    ));
+
+    // nothing is sent after the listener is unset
+    let res = store.send(ADMIN, StoreAction::SetListener { listener: None });
+    assert!(!res.main_failed());
+    let res = buy(&sys, &store, 5, HAT);
+    assert!(replied(&res, 5, sold(TxState::Completed, 0)));
+    assert!(!res.contains(&Log::builder().dest(LISTENER)));
+}
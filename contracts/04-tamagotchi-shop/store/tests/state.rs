@@ -0,0 +1,109 @@
+mod common;
+
+use common::*;
+use gtest::{Log, Program, System};
+use store_io::{
+    AttributeId, CatalogEntry, Category, Currency, StateQuery, StateReply, StoreAction, TxState,
+};
+
+fn catalog(store: &Program<'_>, offset: u32, limit: u32) -> Vec<CatalogEntry> {
+    let StateReply::Catalog { attributes } =
+        read_state(store, StateQuery::Catalog { offset, limit })
+    else {
+        panic!("The store replied to `Catalog` with another reply");
+    };
+    attributes
+}
+
+#[test]
+fn catalog_pages() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+    create_attribute(&store, SWORD, Category::Cosmetic, Currency::FungibleToken);
+    create_attribute(&store, FOOD, Category::Food(10), Currency::Native);
+
+    let res = store.send(ADMIN, StoreAction::RetireAttribute { attribute_id: HAT });
+    assert!(!res.main_failed());
+    let res = store.send(
+        ADMIN,
+        StoreAction::SetPrice {
+            attribute_id: SWORD,
+            price: 2 * PRICE,
+        },
+    );
+    assert!(!res.main_failed());
+
+    let entries = catalog(&store, 0, 10)
+        .into_iter()
+        .map(|entry| {
+            (
+                entry.attribute_id,
+                entry.price,
+                entry.currency,
+                entry.retired,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        [
+            (HAT, PRICE, Currency::Native, true),
+            (SWORD, 2 * PRICE, Currency::FungibleToken, false),
+            (FOOD, PRICE, Currency::Native, false),
+        ]
+    );
+
+    let ids = |offset, limit| {
+        catalog(&store, offset, limit)
+            .into_iter()
+            .map(|entry| entry.attribute_id)
+            .collect::<Vec<AttributeId>>()
+    };
+    assert_eq!(ids(0, 2), [HAT, SWORD]);
+    assert_eq!(ids(2, 2), [FOOD]);
+    assert!(ids(3, 2).is_empty());
+}
+
+#[test]
+fn pending_tx() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::FungibleToken);
+
+    let pending_tx = || {
+        let StateReply::PendingTx { transaction } = read_state(
+            &store,
+            StateQuery::PendingTx {
+                tamagotchi_id: BUYER.into(),
+            },
+        ) else {
+            panic!("The store replied to `PendingTx` with another reply");
+        };
+        transaction
+    };
+    assert!(pending_tx().is_none());
+
+    let res = buy_with_tokens(&store, BUYER, HAT);
+    assert!(res.contains(
+        &Log::builder()
+            .dest(FT)
+            .payload(transfer(0, &store, BUYER, PRICE))
+    ));
+
+    let transaction = pending_tx().expect("The purchase has no transaction");
+    assert_eq!(transaction.id, 0);
+    assert_eq!(transaction.attribute_ids, [HAT]);
+    assert_eq!(transaction.prices, [PRICE]);
+    assert_eq!(transaction.state, TxState::Pending);
+    assert!(transaction.transfer_id.is_some());
+
+    // the full dump is still available
+    let StateReply::Full(state) = read_state(&store, StateQuery::Full) else {
+        panic!("The store replied to `Full` with another reply");
+    };
+    assert_eq!(
+        state.transactions.into_keys().collect::<Vec<_>>(),
+        [BUYER.into()]
+    );
+}
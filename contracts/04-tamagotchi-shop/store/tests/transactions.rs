@@ -0,0 +1,189 @@
+mod common;
+
+use common::*;
+use gear_core::ids::CodeId;
+use gtest::{Log, System};
+use sharded_fungible_token_io::FTokenEvent;
+use std::fs;
+use store_io::{Category, Currency, StoreAction, StoreError, StoreEvent, TxState};
+
+#[test]
+fn failed_transfer_ends_transaction() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::FungibleToken);
+
+    let res = store.send(BUYER, StoreAction::CancelTx);
+    assert!(replied(&res, BUYER, Err(StoreError::NoPendingTx)));
+
+    let res = buy_with_tokens(&store, BUYER, HAT);
+    let transfer_log = Log::builder()
+        .dest(FT)
+        .payload(transfer(0, &store, BUYER, PRICE));
+    assert!(res.contains(&transfer_log));
+
+    // the tokens may already be moved, so the transaction can`t be cancelled
+    let res = store.send(BUYER, StoreAction::CancelTx);
+    assert!(replied(&res, BUYER, Err(StoreError::TxInProgress)));
+    let res = store.send(
+        ADMIN,
+        StoreAction::RemoveTx {
+            tamagotchi_id: BUYER.into(),
+        },
+    );
+    assert!(replied(&res, ADMIN, Err(StoreError::TxInProgress)));
+
+    let res = sys.get_mailbox(FT).reply(transfer_log, FTokenEvent::Err, 0);
+    assert!(replied(&res, BUYER, sold(TxState::Failed, 0)));
+
+    // the failed transaction doesn`t hold up the next purchase
+    let res = buy_with_tokens(&store, BUYER, HAT);
+    assert!(res.contains(
+        &Log::builder()
+            .dest(FT)
+            .payload(transfer(1, &store, BUYER, PRICE))
+    ));
+}
+
+#[test]
+fn paid_purchase_is_redelivered() {
+    let sys = System::new();
+    // the storage code isn`t submitted yet, so the first grant fails
+    let storage_code = fs::read(storage_wasm()).expect("Unable to read the storage binary");
+    let store = init_store_with(&sys, CodeId::generate(&storage_code));
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::Native);
+
+    let res = buy(&sys, &store, BUYER, HAT);
+    assert!(replied(&res, BUYER, sold(TxState::Paid, 0)));
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::DeliverTx {
+            buyer: BUYER.into(),
+        },
+    );
+    assert!(replied(
+        &res,
+        ADMIN,
+        Ok(StoreEvent::TxDelivered {
+            buyer: BUYER.into(),
+            transaction_id: 0,
+            granted: false,
+        })
+    ));
+
+    // the store grants the attributes on its own once the storage can be created
+    sys.submit_code(storage_wasm());
+    sys.spend_blocks(10);
+
+    let res = store.send(
+        BUYER,
+        StoreAction::GetAttributes {
+            tamagotchi_id: BUYER.into(),
+        },
+    );
+    assert!(replied(
+        &res,
+        BUYER,
+        Ok(StoreEvent::Attributes {
+            attributes: [HAT].into(),
+        })
+    ));
+    let res = store.send(
+        ADMIN,
+        StoreAction::DeliverTx {
+            buyer: BUYER.into(),
+        },
+    );
+    assert!(replied(&res, ADMIN, Err(StoreError::NoPaidTx)));
+
+    // the retry gets the outcome without paying again
+    let res = buy(&sys, &store, BUYER, HAT);
+    assert!(replied(&res, BUYER, sold(TxState::Completed, PRICE)));
+    sys.claim_value_from_mailbox(BUYER);
+    assert_eq!(sys.balance_of(store.id()), PRICE);
+}
+
+#[test]
+fn retry_pays_quoted_price() {
+    let sys = System::new();
+    let store = init_store(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Currency::FungibleToken);
+    create_attribute(&store, SWORD, Category::Cosmetic, Currency::FungibleToken);
+
+    let res = buy_with_tokens(&store, BUYER, HAT);
+    let transfer_log = Log::builder()
+        .dest(FT)
+        .payload(transfer(0, &store, BUYER, PRICE));
+    assert!(res.contains(&transfer_log));
+
+    // another purchase waits until the open one is finished
+    let res = buy_with_tokens(&store, BUYER, SWORD);
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::CompletePrevTx { attribute_id: HAT })
+    ));
+    let res = store.send(
+        BUYER,
+        StoreAction::BuyAttributes {
+            attribute_ids: vec![HAT, SWORD],
+        },
+    );
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::CompletePrevTx { attribute_id: HAT })
+    ));
+
+    let res = store.send(
+        ADMIN,
+        StoreAction::SetPrice {
+            attribute_id: HAT,
+            price: 2 * PRICE,
+        },
+    );
+    assert!(!res.main_failed());
+
+    // the retry checks and pays the price quoted when the purchase was started
+    let res = store.send(
+        BUYER,
+        StoreAction::BuyAttribute {
+            attribute_id: HAT,
+            max_price: Some(PRICE / 2),
+            coupon: None,
+        },
+    );
+    assert!(replied(
+        &res,
+        BUYER,
+        Err(StoreError::PriceExceedsMax {
+            price: PRICE,
+            max_price: PRICE / 2,
+        })
+    ));
+    let res = store.send(
+        BUYER,
+        StoreAction::BuyAttribute {
+            attribute_id: HAT,
+            max_price: Some(PRICE),
+            coupon: None,
+        },
+    );
+    assert!(res.contains(&transfer_log));
+
+    // the token contract handles the same transaction ID once, so both transfers reply the same
+    for _ in 0..2 {
+        let res = sys
+            .get_mailbox(FT)
+            .reply(transfer_log.clone(), FTokenEvent::Ok, 0);
+        assert!(replied(&res, BUYER, sold(TxState::Completed, 0)));
+    }
+
+    let res = buy_with_tokens(&store, BUYER, SWORD);
+    assert!(res.contains(
+        &Log::builder()
+            .dest(FT)
+            .payload(transfer(1, &store, BUYER, PRICE))
+    ));
+}
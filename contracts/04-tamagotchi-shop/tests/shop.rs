@@ -0,0 +1,324 @@
+use gtest::{Log, Program, RunResult, System};
+use std::{fs, path::PathBuf};
+use store_io::{
+    AttrMetadata, AttributeId, Category, Currency, Effect, InitStore, Media, Price, Sprite,
+    StoreAction, StoreError, StoreEvent,
+};
+use tamagotchi_shop_io::{Slot, Tamagotchi, TmgAction, TmgError, TmgEvent};
+
+const OWNER: u64 = 10;
+const FT: u64 = 100;
+
+const HAT: AttributeId = 1;
+const SWORD: AttributeId = 2;
+const FOOD: AttributeId = 3;
+// values below the existential deposit of 10 VARA can`t be sent, so prices are whole VARA
+const PRICE: Price = 100_000_000_000_000;
+
+/// Returns the binary of a program built along with the tamagotchi as its build dependency.
+fn wasm(program_dir: &str) -> PathBuf {
+    let program_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(program_dir);
+    let binpath = fs::read_to_string(program_dir.join(".binpath"))
+        .expect("Unable to read the path of the program binary");
+    program_dir.join(binpath.trim()).with_extension("opt.wasm")
+}
+
+/// Returns the store and the tamagotchi of `OWNER`, who also runs the store.
+fn init(sys: &System) -> (Program<'_>, Program<'_>) {
+    let storage_code_id = sys.submit_code(wasm("store/storage"));
+    init_with(sys, storage_code_id.into_bytes())
+}
+
+fn init_with(sys: &System, storage_code_id: [u8; 32]) -> (Program<'_>, Program<'_>) {
+    sys.init_logger();
+    let store = Program::from_file(sys, wasm("store"));
+    let res = store.send(
+        OWNER,
+        InitStore {
+            ft_contract_id: FT.into(),
+            storage_code_id: storage_code_id.into(),
+        },
+    );
+    assert!(!res.main_failed());
+
+    let tamagotchi = Program::current(sys);
+    let res = tamagotchi.send(OWNER, String::from("Tamagotchi Name"));
+    assert!(!res.main_failed());
+
+    (store, tamagotchi)
+}
+
+fn create_attribute(
+    store: &Program<'_>,
+    attribute_id: AttributeId,
+    category: Category,
+    slot: Option<Slot>,
+    effects: Vec<Effect>,
+) {
+    let res = store.send(
+        OWNER,
+        StoreAction::CreateAttribute {
+            attribute_id,
+            attr_metadata: AttrMetadata {
+                title: String::from("Attribute"),
+                description: String::from("Description"),
+                media: Media::Sprite(Sprite::Hat),
+                effects,
+                category,
+                slot,
+            },
+            price: PRICE,
+        },
+    );
+    assert!(
+        res.contains(&Log::builder().dest(OWNER).payload(Ok::<_, StoreError>(
+            StoreEvent::AttributeCreated { attribute_id }
+        )))
+    );
+
+    let res = store.send(
+        OWNER,
+        StoreAction::SetCurrency {
+            attribute_id,
+            currency: Currency::Native,
+        },
+    );
+    assert!(!res.main_failed());
+}
+
+fn buy(
+    sys: &System,
+    tamagotchi: &Program<'_>,
+    store: &Program<'_>,
+    attribute_id: AttributeId,
+) -> RunResult {
+    sys.mint_to(OWNER, PRICE);
+    tamagotchi.send_with_value(
+        OWNER,
+        TmgAction::BuyAttribute {
+            store_id: store.id().into_bytes().into(),
+            attribute_id,
+            max_price: None,
+            coupon: None,
+        },
+        PRICE,
+    )
+}
+
+fn equip(
+    tamagotchi: &Program<'_>,
+    store: &Program<'_>,
+    attribute_id: AttributeId,
+    slot: Slot,
+) -> RunResult {
+    tamagotchi.send(
+        OWNER,
+        TmgAction::Equip {
+            store_id: store.id().into_bytes().into(),
+            attribute_id,
+            slot,
+        },
+    )
+}
+
+fn replied(res: &RunResult, event: TmgEvent) -> bool {
+    res.contains(&Log::builder().dest(OWNER).payload(event))
+}
+
+fn tamagotchi_state(tamagotchi: &Program<'_>) -> Tamagotchi {
+    tamagotchi
+        .read_state(())
+        .expect("Unable to read the tamagotchi state")
+}
+
+#[test]
+fn equipment_slots() {
+    let sys = System::new();
+    let (store, tamagotchi) = init(&sys);
+    create_attribute(&store, HAT, Category::Cosmetic, Some(Slot::Head), vec![]);
+    create_attribute(
+        &store,
+        SWORD,
+        Category::Cosmetic,
+        Some(Slot::Weapon),
+        vec![],
+    );
+
+    assert!(replied(
+        &buy(&sys, &tamagotchi, &store, HAT),
+        TmgEvent::AttributeBought(HAT)
+    ));
+
+    let res = equip(&tamagotchi, &store, SWORD, Slot::Weapon);
+    assert!(replied(
+        &res,
+        TmgEvent::ItemError(TmgError::NotOwned(SWORD))
+    ));
+
+    let res = equip(&tamagotchi, &store, HAT, Slot::Weapon);
+    assert!(replied(
+        &res,
+        TmgEvent::ItemError(TmgError::WrongSlot {
+            attribute_id: HAT,
+            slot: Slot::Weapon,
+        })
+    ));
+
+    let res = equip(&tamagotchi, &store, HAT, Slot::Head);
+    assert!(replied(
+        &res,
+        TmgEvent::Equipped {
+            slot: Slot::Head,
+            attribute_id: HAT,
+        }
+    ));
+    let equipment = tamagotchi_state(&tamagotchi).equipment;
+    assert_eq!(equipment[&Slot::Head].attribute_id, HAT);
+
+    let res = tamagotchi.send(OWNER, TmgAction::Unequip { slot: Slot::Head });
+    assert!(replied(&res, TmgEvent::Unequipped { slot: Slot::Head }));
+    assert!(tamagotchi_state(&tamagotchi).equipment.is_empty());
+}
+
+#[test]
+fn effects() {
+    let sys = System::new();
+    let (store, tamagotchi) = init(&sys);
+    create_attribute(
+        &store,
+        HAT,
+        Category::Cosmetic,
+        Some(Slot::Head),
+        vec![Effect::EnergyCap(5_000)],
+    );
+    create_attribute(
+        &store,
+        SWORD,
+        Category::Cosmetic,
+        Some(Slot::Weapon),
+        vec![Effect::Power(10)],
+    );
+    for (attribute_id, slot) in [(HAT, Slot::Head), (SWORD, Slot::Weapon)] {
+        assert!(replied(
+            &buy(&sys, &tamagotchi, &store, attribute_id),
+            TmgEvent::AttributeBought(attribute_id)
+        ));
+        assert!(replied(
+            &equip(&tamagotchi, &store, attribute_id, slot),
+            TmgEvent::Equipped { slot, attribute_id }
+        ));
+    }
+    assert_eq!(tamagotchi_state(&tamagotchi).power, 10);
+
+    // the equipped attributes take the catalog updates on the next care action
+    let res = store.send(
+        OWNER,
+        StoreAction::UpdateAttribute {
+            attribute_id: SWORD,
+            attr_metadata: AttrMetadata {
+                title: String::from("Attribute"),
+                description: String::from("Description"),
+                media: Media::Sprite(Sprite::Sword),
+                effects: vec![Effect::Power(20)],
+                category: Category::Cosmetic,
+                slot: Some(Slot::Weapon),
+            },
+        },
+    );
+    assert!(!res.main_failed());
+    assert!(replied(
+        &tamagotchi.send(OWNER, TmgAction::Feed),
+        TmgEvent::Fed
+    ));
+    assert_eq!(tamagotchi_state(&tamagotchi).power, 20);
+
+    // the hat lets the tamagotchi store more energy than the basic cap of 10 000
+    for _ in 0..20 {
+        assert!(replied(
+            &tamagotchi.send(OWNER, TmgAction::Sleep),
+            TmgEvent::Slept
+        ));
+    }
+    assert_eq!(tamagotchi_state(&tamagotchi).slept, 15_000);
+}
+
+#[test]
+fn items() {
+    let sys = System::new();
+    let (store, tamagotchi) = init(&sys);
+    create_attribute(&store, FOOD, Category::Food(2_000), None, vec![]);
+    assert!(replied(
+        &buy(&sys, &tamagotchi, &store, FOOD),
+        TmgEvent::AttributeBought(FOOD)
+    ));
+
+    let res = equip(&tamagotchi, &store, FOOD, Slot::Head);
+    assert!(replied(
+        &res,
+        TmgEvent::ItemError(TmgError::Consumable(FOOD))
+    ));
+
+    let use_food = || {
+        tamagotchi.send(
+            OWNER,
+            TmgAction::UseItem {
+                store_id: store.id().into_bytes().into(),
+                attribute_id: FOOD,
+            },
+        )
+    };
+    let fed = tamagotchi_state(&tamagotchi).fed;
+    assert!(replied(&use_food(), TmgEvent::Fed));
+    // the food fills more than the basic feeding
+    assert!(tamagotchi_state(&tamagotchi).fed > fed + 1_000);
+
+    assert!(replied(
+        &use_food(),
+        TmgEvent::ItemError(TmgError::Store(StoreError::NotOwned { attribute_id: FOOD }))
+    ));
+}
+
+#[test]
+fn store_errors() {
+    let sys = System::new();
+    let (store, tamagotchi) = init(&sys);
+
+    // the tamagotchi passes the store error on and returns the value to its owner
+    let res = buy(&sys, &tamagotchi, &store, HAT);
+    assert!(replied(
+        &res,
+        TmgEvent::ErrorDuringPurchase(TmgError::Store(StoreError::AttributeNotFound {
+            attribute_id: HAT
+        }))
+    ));
+    sys.claim_value_from_mailbox(OWNER);
+    assert_eq!(sys.balance_of(OWNER), PRICE);
+    assert_eq!(sys.balance_of(tamagotchi.id()), 0);
+}
+
+#[test]
+fn pending_purchase() {
+    let sys = System::new();
+    // no storage can be created from a missing code, so the store keeps the payment ungranted
+    let (store, tamagotchi) = init_with(&sys, [0xff; 32]);
+    create_attribute(&store, HAT, Category::Cosmetic, Some(Slot::Head), vec![]);
+    create_attribute(
+        &store,
+        SWORD,
+        Category::Cosmetic,
+        Some(Slot::Weapon),
+        vec![],
+    );
+
+    let res = buy(&sys, &tamagotchi, &store, HAT);
+    assert!(replied(
+        &res,
+        TmgEvent::ErrorDuringPurchase(TmgError::PurchasePending)
+    ));
+
+    let res = buy(&sys, &tamagotchi, &store, SWORD);
+    assert!(replied(&res, TmgEvent::CompletePrevPurchase(HAT)));
+    // only the paid purchase keeps the value
+    sys.claim_value_from_mailbox(OWNER);
+    assert_eq!(sys.balance_of(OWNER), PRICE);
+}
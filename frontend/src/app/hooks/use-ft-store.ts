@@ -1,20 +1,58 @@
-import { useEffect } from 'react'
+import { useEffect, useMemo } from 'react'
 import { useReadState } from './use-metadata'
 import metaStore from '@/assets/meta/meta-store.txt'
+import metaStoreStorage from '@/assets/meta/meta-store-storage.txt'
 import { ENV } from '@/app/consts'
 import { useFTStore, useLessons, useTamagotchi } from '@/app/context'
-import { ItemsStoreResponse } from '@/app/types/ft-store'
+import {
+  StorageAttributesReply,
+  StoreCatalogReply,
+  StoreStorageReply,
+} from '@/app/types/ft-store'
 import { getStoreItems } from '@/app/utils'
 
+// the store pages its catalog, one page is enough for the lessons
+const CATALOG_LIMIT = 100
+
 export function useItemsStore() {
   const { setTamagotchiItems } = useTamagotchi()
   const { lesson } = useLessons()
   const { setItems, setStore } = useFTStore()
-  const state = useReadState<ItemsStoreResponse>({
+  const tamagotchiId = lesson?.programId
+
+  const catalogQuery = useMemo(
+    () => ({ Catalog: { offset: 0, limit: CATALOG_LIMIT } }),
+    []
+  )
+  const catalog = useReadState<StoreCatalogReply>({
     programId: ENV.store,
     meta: metaStore,
+    payload: catalogQuery,
+  }).state
+
+  // the attributes are held by a storage program, so the store is asked for it first
+  const storageQuery = useMemo(
+    () => ({ AttributesStorageOf: { tamagotchiId } }),
+    [tamagotchiId]
+  )
+  const storage = useReadState<StoreStorageReply>({
+    programId: tamagotchiId ? ENV.store : undefined,
+    meta: metaStore,
+    payload: storageQuery,
+  }).state
+
+  const attributesQuery = useMemo(
+    () => ({ AttributesOf: { tamagotchiId } }),
+    [tamagotchiId]
+  )
+  const owned = useReadState<StorageAttributesReply>({
+    programId: storage?.AttributesStorageOf.storage ?? undefined,
+    meta: metaStoreStorage,
+    payload: attributesQuery,
   }).state
 
+  const state = catalog?.Catalog.attributes
+
   useEffect(() => {
     setStore(state)
 
@@ -25,9 +63,12 @@ export function useItemsStore() {
 
   useEffect(() => {
     if (lesson && lesson.step > 3 && state) {
-      const { programId } = lesson
-      setItems(getStoreItems(state, programId).store)
-      setTamagotchiItems(getStoreItems(state, programId).tamagotchi)
+      const { store, tamagotchi } = getStoreItems(
+        state,
+        Object.keys(owned?.AttributesOf.attributes ?? {})
+      )
+      setItems(store)
+      setTamagotchiItems(tamagotchi)
     } else {
       setItems([])
       setTamagotchiItems([])
@@ -36,5 +77,5 @@ export function useItemsStore() {
       setItems([])
       setTamagotchiItems([])
     }
-  }, [lesson, state])
+  }, [lesson, state, owned])
 }
@@ -3,6 +3,7 @@ import { getStateMetadata, ProgramMetadata, StateMetadata } from '@gear-js/api'
 import { Buffer } from 'buffer'
 import { useAlert, useReadFullState } from '@gear-js/react-hooks'
 import { HexString } from '@polkadot/util/types'
+import { AnyJson } from '@polkadot/types/types'
 
 export function useProgramMetadata(source: string) {
   const alert = useAlert()
@@ -50,10 +51,12 @@ export function useStateMetadata(source: string) {
 export function useReadState<T>({
   programId,
   meta,
+  payload = '0x',
 }: {
   programId?: HexString
   meta: string
+  payload?: AnyJson
 }) {
   const metadata = useProgramMetadata(meta)
-  return useReadFullState<T>(programId, metadata, '0x')
+  return useReadFullState<T>(programId, metadata, payload)
 }
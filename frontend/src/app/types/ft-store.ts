@@ -2,10 +2,22 @@ import { HexString } from '@polkadot/util/types'
 
 export type StoreItemsNames = 'sword' | 'hat' | 'bag' | 'glasses'
 
+type StoreItemSprite = 'Sword' | 'Hat' | 'Bag' | 'Glasses'
+
+export type StoreItemMedia =
+  | { Ipfs: string }
+  | { Https: string }
+  | { Sprite: StoreItemSprite }
+
 type StoreItemDescription = {
   description: string
-  media: StoreItemsNames
+  media: StoreItemMedia
   title: string
+  category:
+    | 'Cosmetic'
+    | { Food: string }
+    | { Toy: string }
+    | { Pillow: string }
 }
 
 export type StoreItemType = {
@@ -15,11 +27,24 @@ export type StoreItemType = {
   isBought: boolean
 }
 
-export type ItemsStoreResponse = {
-  admin: HexString
-  attributes: Record<string, [StoreItemDescription, string]>
-  ftContractId: HexString
-  owners: Record<HexString, string[]>
-  transactionId: string
-  transactions: {}
+export type StoreCatalogEntry = {
+  attributeId: string
+  attrMetadata: StoreItemDescription
+  price: string
+  currency: 'FungibleToken' | 'Native' | { Token: HexString }
+  retired: boolean
+}
+
+export type ItemsStoreResponse = StoreCatalogEntry[]
+
+export type StoreCatalogReply = {
+  Catalog: { attributes: StoreCatalogEntry[] }
+}
+
+export type StoreStorageReply = {
+  AttributesStorageOf: { storage: HexString | null }
+}
+
+export type StorageAttributesReply = {
+  AttributesOf: { attributes: Record<string, string> }
 }
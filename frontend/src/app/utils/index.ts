@@ -1,5 +1,4 @@
 import { Account, AlertContainerFactory } from '@gear-js/react-hooks'
-import { HexString } from '@polkadot/util/types'
 import {
   NotificationResponseTypes,
   NotificationType,
@@ -8,6 +7,7 @@ import {
 import { LOCAL_STORAGE } from '@/app/consts'
 import type {
   ItemsStoreResponse,
+  StoreItemMedia,
   StoreItemsNames,
   StoreItemType,
 } from '@/app/types/ft-store'
@@ -61,22 +61,37 @@ export const getNotificationTypeValue = (
   }
 }
 
+export const getSpriteName = (
+  media: StoreItemMedia
+): StoreItemsNames | undefined =>
+  'Sprite' in media
+    ? (media.Sprite.toLowerCase() as StoreItemsNames)
+    : undefined
+
+export const getMediaUrl = (media: StoreItemMedia) => {
+  if ('Ipfs' in media) return `https://ipfs.io/ipfs/${media.Ipfs}`
+  if ('Https' in media) return media.Https
+}
+
 export const getStoreItems = (
   state: ItemsStoreResponse,
-  programId: HexString
+  ownedIds: string[]
 ) => {
   if (!state) return { store: [], tamagotchi: [] }
   const store: StoreItemType[] = []
   const tamagotchi: StoreItemsNames[] = []
-  for (const idx in state.attributes) {
-    const isBought: boolean = state.owners[programId]?.includes(idx)
+  for (const { attributeId, attrMetadata, price, retired } of state) {
+    const isBought: boolean = ownedIds.includes(attributeId)
+    const sprite = getSpriteName(attrMetadata.media)
 
-    if (isBought) tamagotchi.push(state.attributes[+idx][0].media)
+    if (isBought && sprite) tamagotchi.push(sprite)
+    // retired attributes are listed only to resolve what tamagotchis already own
+    if (retired) continue
 
     store.push({
-      id: idx,
-      amount: state.attributes[+idx][1],
-      description: state.attributes[+idx][0],
+      id: attributeId,
+      amount: price,
+      description: attrMetadata,
       isBought,
     })
   }
@@ -90,8 +105,9 @@ export const getAttributesById = (
   if (ids.length < 1) return []
 
   const result: StoreItemsNames[] = []
-  for (const id in state.attributes) {
-    if (ids.includes(id)) result.push(state.attributes[+id][0].media)
+  for (const { attributeId, attrMetadata } of state) {
+    const sprite = getSpriteName(attrMetadata.media)
+    if (ids.includes(attributeId) && sprite) result.push(sprite)
   }
   return result
 }
@@ -10,6 +10,7 @@ import { PaymentErrorPopup } from '@/components/popups/payment-error-popup'
 import { withoutCommas } from '@gear-js/react-hooks'
 import { useHandleCalculateGas } from '@/app/hooks/use-handle-calculate-gas'
 import { useCheckBalance } from '@/app/hooks/use-check-balance'
+import { getMediaUrl, getSpriteName } from '@/app/utils'
 
 export const StoreItemCard = ({ item }: { item: StoreItemType }) => {
   const { id, amount, description, isBought } = item
@@ -19,6 +20,9 @@ export const StoreItemCard = ({ item }: { item: StoreItemType }) => {
   const calculateGas = useHandleCalculateGas(ENV.battle, lessonMeta)
   const { checkBalance } = useCheckBalance()
   const { setIsPending, isPending } = useApp()
+  const sprite = getSpriteName(description.media)
+  // consumables are bought again once used up
+  const isOwned = isBought && description.category === 'Cosmetic'
 
   const onError = () => setIsPending(false)
   const onSuccess = () => setIsPending(false)
@@ -66,11 +70,19 @@ export const StoreItemCard = ({ item }: { item: StoreItemType }) => {
     <article>
       <div className="flex flex-col py-10 px-8 bg-white/5 aspect-[347/230] rounded-2xl">
         <div className="flex justify-center">
-          <SpriteIcon
-            name={'item-' + description.media.toLowerCase()}
-            section="tamagotchi"
-            className="w-35 h-35"
-          />
+          {sprite ? (
+            <SpriteIcon
+              name={'item-' + sprite}
+              section="tamagotchi"
+              className="w-35 h-35"
+            />
+          ) : (
+            <img
+              src={getMediaUrl(description.media)}
+              alt={description.title}
+              className="w-35 h-35 object-contain"
+            />
+          )}
         </div>
         <h2 className="mt-auto text-center font-kanit font-semibold text-xl tracking-[0.04em]">
           {description.title}
@@ -93,7 +105,7 @@ export const StoreItemCard = ({ item }: { item: StoreItemType }) => {
           text="Buy"
           icon={() => <SpriteIcon name="cart" className="w-4 h-4" />}
           onClick={() => handler(+withoutCommas(amount))}
-          disabled={isBought || isPending}
+          disabled={isOwned || isPending}
         />
       </div>
       {open && <PaymentErrorPopup close={() => setOpen(false)} />}